@@ -1,5 +1,8 @@
 use bevy::prelude::*;
 use bevy_asset_loader::AssetCollection;
+use bevy_kira_audio::AudioSource;
+use crate::gameplay::balance::Balance;
+use crate::gameplay::species::SpeciesTable;
 
 #[derive(AssetCollection)]
 pub struct GameplayAssets {
@@ -12,55 +15,45 @@ pub struct GameplayAssets {
     #[asset(path = "gameplay/connector.png")]
     pub connector: Handle<Image>,
 
-    #[asset(path = "gameplay/converter-red-green.png")]
-    pub converter_red_green: Handle<Image>,
+    #[asset(path = "gameplay/default.species.ron")]
+    pub species: Handle<SpeciesTable>,
 
-    #[asset(path = "gameplay/converter-red-blue.png")]
-    pub converter_red_blue: Handle<Image>,
-    #[asset(path = "gameplay/converter-green-red.png")]
-    pub converter_green_red: Handle<Image>,
-    #[asset(path = "gameplay/converter-green-blue.png")]
-    pub converter_green_blue: Handle<Image>,
-    #[asset(path = "gameplay/converter-blue-red.png")]
-    pub converter_blue_red: Handle<Image>,
-    #[asset(path = "gameplay/converter-blue-green.png")]
-    pub converter_blue_green: Handle<Image>,
+    #[asset(path = "gameplay/default.balance.ron")]
+    pub balance: Handle<Balance>,
+}
+#[derive(AssetCollection)]
+pub struct AudioAssets {
+    #[asset(path = "audio/hit.wav")]
+    pub hit: Handle<AudioSource>,
 
-    #[asset(path = "gameplay/cannon-red.png")]
-    pub cannon_red: Handle<Image>,
-    #[asset(path = "gameplay/cannon-green.png")]
-    pub cannon_green: Handle<Image>,
-    #[asset(path = "gameplay/cannon-blue.png")]
-    pub cannon_blue: Handle<Image>,
+    #[asset(path = "audio/kill.wav")]
+    pub kill: Handle<AudioSource>,
 
-    #[asset(path = "gameplay/bullet-red.png")]
-    pub bullet_red: Handle<Image>,
-    #[asset(path = "gameplay/bullet-green.png")]
-    pub bullet_green: Handle<Image>,
-    #[asset(path = "gameplay/bullet-blue.png")]
-    pub bullet_blue: Handle<Image>,
+    #[asset(path = "audio/place.wav")]
+    pub place: Handle<AudioSource>,
 
-    #[asset(path = "gameplay/monster-red.png")]
-    pub monster_red: Handle<Image>,
-    #[asset(path = "gameplay/monster-green.png")]
-    pub monster_green: Handle<Image>,
-    #[asset(path = "gameplay/monster-blue.png")]
-    pub monster_blue: Handle<Image>,
+    #[asset(path = "audio/denied.wav")]
+    pub denied: Handle<AudioSource>,
+
+    #[asset(path = "audio/defeat.wav")]
+    pub defeat: Handle<AudioSource>,
 }
 
 #[derive(AssetCollection)]
-pub struct UIAssets {
-    #[asset(path = "red-square.png")]
-    pub red_square: Handle<Image>,
-    #[asset(path = "green-triangle.png")]
-    pub green_triangle: Handle<Image>,
-    #[asset(path = "blue-circle.png")]
-    pub blue_circle: Handle<Image>,
+pub struct MusicAssets {
+    #[asset(path = "music/menu.wav")]
+    pub menu: Handle<AudioSource>,
+
+    /// Gameplay stems, all of the same length so they stay in sync.
+    #[asset(path = "music/gameplay-base.wav")]
+    pub gameplay_base: Handle<AudioSource>,
 
-    #[asset(path = "red-square-small.png")]
-    pub red_square_small: Handle<Image>,
-    #[asset(path = "green-triangle-small.png")]
-    pub green_triangle_small: Handle<Image>,
-    #[asset(path = "blue-circle-small.png")]
-    pub blue_circle_small: Handle<Image>,
-}
\ No newline at end of file
+    #[asset(path = "music/gameplay-tension.wav")]
+    pub gameplay_tension: Handle<AudioSource>,
+
+    #[asset(path = "music/gameplay-danger.wav")]
+    pub gameplay_danger: Handle<AudioSource>,
+
+    #[asset(path = "music/lose.wav")]
+    pub lose: Handle<AudioSource>,
+}
@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+use bevy_kira_audio::{AudioApp, AudioChannel};
+use crate::assets::AudioAssets;
+use crate::gameplay::events::{CannonFired, CoreDestroyed, ItemPlaced, MonsterHit, MonsterKilled, PlacementDenied};
+use crate::gameplay::species::SpeciesTable;
+use crate::settings::Settings;
+
+/// Channel for short one-off sounds, so they can be controlled separately from the music.
+pub struct SfxChannel;
+
+pub fn register_systems(app: &mut App) {
+    app.add_audio_channel::<SfxChannel>();
+
+    // Runs in every state, so the defeat sound still plays after switching to `Lose`
+    app.add_system(play_sfx)
+        .add_system(sfx_volume);
+}
+
+/// Applies the volume settings to the sound effects channel, also when they are first loaded.
+pub fn sfx_volume(sfx: Res<AudioChannel<SfxChannel>>,
+                  settings: Res<Settings>)
+{
+    if settings.is_changed() {
+        sfx.set_volume(settings.effective_sfx_volume());
+    }
+}
+
+/// Plays a sound for each gameplay event sent this frame.
+pub fn play_sfx(sfx: Res<AudioChannel<SfxChannel>>,
+                audio_assets: Option<Res<AudioAssets>>,
+                species_table: Option<Res<SpeciesTable>>,
+                mut fired: EventReader<CannonFired>,
+                mut hits: EventReader<MonsterHit>,
+                mut kills: EventReader<MonsterKilled>,
+                mut placed: EventReader<ItemPlaced>,
+                mut denied: EventReader<PlacementDenied>,
+                mut core_destroyed: EventReader<CoreDestroyed>)
+{
+    let (audio_assets, species_table) = match (audio_assets, species_table) {
+        (Some(audio_assets), Some(species_table)) => (audio_assets, species_table),
+        _ => return,
+    };
+
+    // Many things often happen in the same frame, one sound of each kind is enough for all of them
+    let mut fired_species = Vec::new();
+    for event in fired.iter() {
+        if !fired_species.contains(&event.species) {
+            fired_species.push(event.species);
+            sfx.play(species_table.get(event.species).fire_sound.clone());
+        }
+    }
+
+    if hits.iter().count() > 0 {
+        sfx.play(audio_assets.hit.clone());
+    }
+    if kills.iter().count() > 0 {
+        sfx.play(audio_assets.kill.clone());
+    }
+
+    for _ in placed.iter() {
+        sfx.play(audio_assets.place.clone());
+    }
+    if denied.iter().count() > 0 {
+        sfx.play(audio_assets.denied.clone());
+    }
+    if core_destroyed.iter().count() > 0 {
+        sfx.play(audio_assets.defeat.clone());
+    }
+}
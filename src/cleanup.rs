@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+use crate::GameState;
+
+/// Despawns the entity and its children when the game leaves this state.
+///
+/// Only needed on the root of a hierarchy. Leaving a state means replacing or popping it,
+/// so anything owned by `Gameplay` survives while `Paused` is pushed on top of it.
+#[derive(Component)]
+pub struct StateScoped(pub GameState);
+
+pub fn register_systems(app: &mut App) {
+    let states = [
+        GameState::LoadingAssets,
+        GameState::LoadingFailed,
+        GameState::Gameplay,
+        GameState::Paused,
+        GameState::Lose,
+    ];
+
+    for state in states {
+        let exited = state.clone();
+
+        app.add_system_set(SystemSet::on_exit(state).with_system(
+            move |mut commands: Commands, entities: Query<(Entity, &StateScoped)>| {
+                for (entity, scope) in entities.iter() {
+                    if scope.0 == exited {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                }
+            }
+        ));
+    }
+}
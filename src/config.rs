@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Loads a config file saved with [`save`].
+///
+/// Returns `None` if the file doesn't exist or can't be parsed.
+pub fn load<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let contents = read(name)?;
+
+    match ron::de::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Ignoring invalid config file {}: {}", name, err);
+            None
+        },
+    }
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) {
+    let contents = match ron::ser::to_string_pretty(value, default()) {
+        Ok(contents) => contents,
+        Err(err) => {
+            error!("Failed to serialize config file {}: {}", name, err);
+            return;
+        },
+    };
+
+    write(name, &contents);
+}
+
+/// Config files are kept next to the executable, so they travel together with the game folder.
+#[cfg(not(target_arch = "wasm32"))]
+fn path(name: &str) -> Option<std::path::PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+
+    Some(exe.parent()?.join(name))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(name: &str) -> Option<String> {
+    std::fs::read_to_string(path(name)?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(name: &str, contents: &str) {
+    let result = match path(name) {
+        Some(path) => std::fs::write(path, contents),
+        None => return,
+    };
+
+    if let Err(err) = result {
+        error!("Failed to save config file {}: {}", name, err);
+    }
+}
+
+// There is no file system in the browser, so the configs go into the local storage instead
+#[cfg(target_arch = "wasm32")]
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(name: &str) -> Option<String> {
+    storage()?.get_item(name).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(name: &str, contents: &str) {
+    if let Some(storage) = storage() {
+        if storage.set_item(name, contents).is_err() {
+            error!("Failed to save config file {}", name);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use bevy::asset::AssetServerSettings;
 use bevy::prelude::*;
 use bevy_asset_loader::AssetLoader;
 use bevy_ecs_tilemap::TilemapPlugin;
@@ -5,9 +6,9 @@ use bevy_inspector_egui::WorldInspectorPlugin;
 use bevy_kira_audio::AudioPlugin;
 use bevy_ninepatch::NinePatchPlugin;
 use bevy_tweening::TweeningPlugin;
-use crate::assets::{GameplayAssets, UIAssets};
-use crate::{gameplay, palette};
-use crate::loading::{loading_end, loading_start};
+use crate::assets::{AudioAssets, GameplayAssets, MusicAssets};
+use crate::{audio, cleanup, gameplay, music, input, localization, palette, pause, settings, theme};
+use crate::loading::{loading_failed_start, loading_progress, loading_start, track_assets};
 use crate::lose::lose_start;
 
 pub fn run(app: &mut App) {
@@ -16,6 +17,11 @@ pub fn run(app: &mut App) {
         resizable: true,
         ..Default::default()
     })
+    // Allows tweaking the balance while the game is running
+    .insert_resource(AssetServerSettings {
+        watch_for_changes: cfg!(not(target_arch = "wasm32")),
+        ..Default::default()
+    })
     .insert_resource(ClearColor(palette::BLACK))
     .add_plugins(DefaultPlugins)
     .add_plugin(AudioPlugin)
@@ -29,15 +35,27 @@ pub fn run(app: &mut App) {
     AssetLoader::new(GameState::LoadingAssets)
         .continue_to_state(GameState::Gameplay)
         .with_collection::<GameplayAssets>()
-        .with_collection::<UIAssets>()
+        .with_collection::<AudioAssets>()
+        .with_collection::<MusicAssets>()
         .build(app);
 
     app.add_state(GameState::LoadingAssets)
         .add_startup_system(preload_assets)
-        .add_system_set(SystemSet::on_enter(GameState::LoadingAssets).with_system(loading_start))
-        .add_system_set(SystemSet::on_exit(GameState::LoadingAssets).with_system(loading_end));
+        .add_system_set(SystemSet::on_enter(GameState::LoadingAssets)
+            .with_system(loading_start)
+            .with_system(track_assets.exclusive_system()))
+        .add_system_set(SystemSet::on_update(GameState::LoadingAssets).with_system(loading_progress))
+        .add_system_set(SystemSet::on_enter(GameState::LoadingFailed).with_system(loading_failed_start));
 
+    cleanup::register_systems(app);
+    input::register_systems(app);
+    settings::register_systems(app);
+    localization::register_systems(app);
+    theme::register_systems(app);
     gameplay::register_systems(app);
+    audio::register_systems(app);
+    music::register_systems(app);
+    pause::register_systems(app);
 
     app.add_system_set(SystemSet::on_enter(GameState::Lose).with_system(lose_start));
 
@@ -63,6 +81,10 @@ pub struct PreloadedAssets {
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 pub enum GameState {
     LoadingAssets,
+    /// An asset failed to load, the game can't go on.
+    LoadingFailed,
     Gameplay,
+    /// Pushed on top of `Gameplay`, shows the controls screen.
+    Paused,
     Lose,
 }
@@ -0,0 +1,97 @@
+use anyhow::anyhow;
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+use std::collections::HashMap;
+use crate::gameplay::components::{Item, Money, Species};
+use crate::gameplay::species::SpeciesTable;
+
+/// Gameplay tuning values, loaded from a `.balance.ron` file.
+///
+/// The file is watched for changes on desktop, so the numbers can be tweaked while the game is running.
+#[derive(Deserialize, TypeUuid, Clone)]
+#[uuid = "c0a3e5d4-8b7f-4e2a-9f61-2d5b8e4a1c39"]
+pub struct Balance {
+    pub start_money: u32,
+    pub kill_bounty: u32,
+    /// Price of a connector: the name of the species it is paid in and the amount.
+    pub connector_cost: (String, u32),
+    pub monster_health: i32,
+    /// How much health each next monster gets on top of the previous one.
+    pub monster_health_growth: i32,
+    pub first_spawn_delay: f32,
+    /// Minimum and maximum delay between monster spawns.
+    pub spawn_interval: (f32, f32),
+    /// For how long a building action can be undone, in seconds.
+    pub undo_window: f32,
+    /// Per-species values, keyed by the species name from the species table.
+    pub species: HashMap<String, SpeciesBalance>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct SpeciesBalance {
+    pub speed: f32,
+    pub bullet_speed: f32,
+    pub fire_cooldown: f32,
+    /// Damage dealt to monsters of this species by a single bullet.
+    pub damage: i32,
+    /// Price of a cannon of this species, paid in the currency of the same species.
+    pub cannon_cost: u32,
+}
+
+impl Balance {
+    pub fn species(&self, species_table: &SpeciesTable, species: Species) -> &SpeciesBalance {
+        &self.species[&species_table.get(species).name]
+    }
+
+    pub fn connector_cost(&self, species_table: &SpeciesTable) -> Money {
+        let (name, amount) = &self.connector_cost;
+
+        Money::new(species_table.find(name).unwrap(), *amount)
+    }
+
+    /// Price of an item in the buy panel, converters aren't for sale yet.
+    pub fn item_cost(&self, species_table: &SpeciesTable, item: Item) -> Option<Money> {
+        match item {
+            Item::Connector => Some(self.connector_cost(species_table)),
+            Item::Cannon(species) => Some(Money::new(species, self.species(species_table, species).cannon_cost)),
+            Item::Converter { .. } => None,
+        }
+    }
+
+    /// Checks that every species in the table has its balance values, and that prices are in known currencies.
+    pub fn validate(&self, species_table: &SpeciesTable) -> Result<(), anyhow::Error> {
+        if species_table.find(&self.connector_cost.0).is_none() {
+            return Err(anyhow!("connectors cost unknown currency {}", self.connector_cost.0));
+        }
+
+        for species in species_table.iter() {
+            let name = &species_table.get(species).name;
+
+            if !self.species.contains_key(name) {
+                return Err(anyhow!("no balance values for species {}", name));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct BalanceLoader;
+
+impl AssetLoader for BalanceLoader {
+    fn load<'a>(&'a self, bytes: &'a [u8], load_context: &'a mut LoadContext) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let balance: Balance = ron::de::from_bytes(bytes)?;
+
+            load_context.set_default_asset(LoadedAsset::new(balance));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["balance.ron"]
+    }
+}
@@ -0,0 +1,159 @@
+use bevy::input::mouse::MouseMotion;
+use bevy::math::vec2;
+use bevy::prelude::*;
+use crate::gameplay::components::*;
+use crate::gameplay::pointer::MapGeometry;
+use crate::gameplay::resources::GamepadCursor;
+use crate::input::{Action, ActionState};
+
+/// Screen pixels per world unit. Only whole numbers are used, so the pixel art stays crisp.
+pub const DEFAULT_ZOOM: f32 = 3.0;
+const MIN_ZOOM: f32 = 1.0;
+const MAX_ZOOM: f32 = 6.0;
+
+/// Keyboard pan speed, in screen pixels per second.
+const PAN_SPEED: f32 = 600.0;
+/// Distance from the window edge where the cursor starts scrolling the view, in screen pixels.
+const EDGE_SCROLL_MARGIN: f32 = 8.0;
+/// How close the gamepad cursor can get to the window edge before the view follows it, in screen pixels.
+const GAMEPAD_CURSOR_MARGIN: f32 = 48.0;
+
+pub fn pan_camera(mut camera: Query<(&mut Transform, &OrthographicProjection), With<MainCamera>>,
+                  geometry: Res<MapGeometry>,
+                  actions: Res<ActionState>,
+                  mut mouse_motion: EventReader<MouseMotion>,
+                  gamepad_cursor: Res<GamepadCursor>,
+                  windows: Res<Windows>,
+                  time: Res<Time>)
+{
+    let (mut transform, projection) = camera.single_mut();
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let window_size = vec2(window.width(), window.height());
+
+    // Everything below is in screen pixels, converted to world units at the end
+    let mut offset = Vec2::ZERO;
+
+    let directions = [
+        (Action::PanUp, Vec2::Y),
+        (Action::PanDown, -Vec2::Y),
+        (Action::PanLeft, -Vec2::X),
+        (Action::PanRight, Vec2::X),
+    ];
+    for (action, direction) in directions {
+        if actions.pressed(action) {
+            offset += direction * PAN_SPEED * time.delta_seconds();
+        }
+    }
+
+    let dragged = mouse_motion.iter().fold(Vec2::ZERO, |dragged, motion| dragged + motion.delta);
+    if actions.pressed(Action::DragPan) {
+        // Mouse motion goes downwards, the world goes upwards
+        offset -= vec2(dragged.x, -dragged.y);
+    }
+
+    if gamepad_cursor.active {
+        let cursor = (gamepad_cursor.position - transform.translation.truncate()) / projection.scale + window_size / 2.0;
+
+        let min = Vec2::splat(GAMEPAD_CURSOR_MARGIN);
+        let max = window_size - GAMEPAD_CURSOR_MARGIN;
+        offset += cursor - cursor.clamp(min, max.max(min));
+    } else if let Some(cursor) = window.cursor_position() {
+        if !actions.pressed(Action::DragPan) {
+            let mut edge = Vec2::ZERO;
+            if cursor.x < EDGE_SCROLL_MARGIN {
+                edge.x = -1.0;
+            } else if cursor.x > window_size.x - EDGE_SCROLL_MARGIN {
+                edge.x = 1.0;
+            }
+            if cursor.y < EDGE_SCROLL_MARGIN {
+                edge.y = -1.0;
+            } else if cursor.y > window_size.y - EDGE_SCROLL_MARGIN {
+                edge.y = 1.0;
+            }
+
+            offset += edge * PAN_SPEED * time.delta_seconds();
+        }
+    }
+
+    let position = transform.translation.truncate() + offset * projection.scale;
+    let position = clamp_to_map(position, window_size * projection.scale, &geometry);
+
+    // Moving by whole screen pixels keeps the pixel grid aligned with the screen
+    let zoom = 1.0 / projection.scale;
+    transform.translation.x = (position.x * zoom).round() / zoom;
+    transform.translation.y = (position.y * zoom).round() / zoom;
+}
+
+pub fn zoom_camera(mut camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+                   geometry: Res<MapGeometry>,
+                   ghosts: Query<(), With<ItemPlacementGhost>>,
+                   actions: Res<ActionState>,
+                   gamepad_cursor: Res<GamepadCursor>,
+                   windows: Res<Windows>)
+{
+    // The mouse wheel both zooms and rotates by default, while placing an item it only rotates
+    let rotating = actions.just_pressed(Action::RotateClockwise) || actions.just_pressed(Action::RotateCounterClockwise);
+    if rotating && !ghosts.is_empty() {
+        return;
+    }
+
+    let mut steps = 0.0;
+    if actions.just_pressed(Action::ZoomIn) {
+        steps += 1.0;
+    }
+    if actions.just_pressed(Action::ZoomOut) {
+        steps -= 1.0;
+    }
+    if steps == 0.0 {
+        return;
+    }
+
+    let (mut transform, mut projection) = camera.single_mut();
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let window_size = vec2(window.width(), window.height());
+
+    let old_zoom = (1.0 / projection.scale).round();
+    let new_zoom = (old_zoom + steps).clamp(MIN_ZOOM, MAX_ZOOM);
+    if new_zoom == old_zoom {
+        return;
+    }
+
+    // Keep the point under the cursor in place
+    let position = transform.translation.truncate();
+    let anchor = if gamepad_cursor.active {
+        Some(gamepad_cursor.position)
+    } else {
+        window.cursor_position().map(|cursor| position + (cursor - window_size / 2.0) / old_zoom)
+    };
+
+    let position = match anchor {
+        Some(anchor) => anchor + (position - anchor) * old_zoom / new_zoom,
+        None => position,
+    };
+    let position = clamp_to_map(position, window_size / new_zoom, &geometry);
+
+    projection.scale = 1.0 / new_zoom;
+    transform.translation.x = (position.x * new_zoom).round() / new_zoom;
+    transform.translation.y = (position.y * new_zoom).round() / new_zoom;
+}
+
+/// Keeps a view of `view_size` centered at `position` inside the tilemap.
+///
+/// If the view is larger than the map, the map is centered instead.
+fn clamp_to_map(position: Vec2, view_size: Vec2, geometry: &MapGeometry) -> Vec2 {
+    let (map_min, map_max) = geometry.bounds();
+
+    let min = map_min + view_size / 2.0;
+    let max = map_max - view_size / 2.0;
+
+    vec2(
+        if min.x < max.x { position.x.clamp(min.x, max.x) } else { (map_min.x + map_max.x) / 2.0 },
+        if min.y < max.y { position.y.clamp(min.y, max.y) } else { (map_min.y + map_max.y) / 2.0 },
+    )
+}
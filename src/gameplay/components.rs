@@ -1,7 +1,6 @@
+use std::f32::consts::PI;
 use bevy::prelude::*;
-use crate::palette;
-
-pub const DEFAULT_HEALTH: i32 = 22;
+use crate::gameplay::placement::PlacementError;
 
 #[derive(Component)]
 pub struct MainCamera;
@@ -12,12 +11,21 @@ pub struct CoreSpinner;
 #[derive(Component)]
 pub struct Health (pub i32);
 
-#[derive(Copy, Clone, PartialEq)]
-pub enum Species {
-    Red,
-    Green,
-    Blue,
-}
+/// Health the entity was spawned with.
+#[derive(Component)]
+pub struct MaxHealth(pub i32);
+
+/// Background of a health bar, child of the entity whose health it shows.
+#[derive(Component)]
+pub struct HealthBar;
+
+/// Coloured part of a health bar, child of the [`HealthBar`].
+#[derive(Component)]
+pub struct HealthBarFill;
+
+/// Index of a species in the [`SpeciesTable`](crate::gameplay::species::SpeciesTable).
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Species(pub usize);
 
 #[derive(Component)]
 pub struct WalletDisplay(pub Species);
@@ -40,15 +48,13 @@ impl Money {
 #[derive(Component, Copy, Clone)]
 pub enum Item {
     Connector,
-    RedCannon,
-    GreenCannon,
-    BlueCannon,
-    ConverterRedGreen,
-    ConverterRedBlue,
-    ConverterGreenRed,
-    ConverterGreenBlue,
-    ConverterBlueRed,
-    ConverterBlueGreen,
+    Cannon(Species),
+    /// Not for sale yet, the buy panel only offers connectors and cannons.
+    #[allow(dead_code)]
+    Converter {
+        from: Species,
+        to: Species,
+    },
 }
 
 #[derive(Component)]
@@ -57,9 +63,107 @@ pub struct BuyItemButton {
     pub item: Item,
 }
 
+/// Position of an item in the buy panel, also attached to the ghost of the item.
+#[derive(Component, Copy, Clone, PartialEq, Eq)]
+pub struct ItemSlot(pub usize);
+
+/// Shows the button selecting the item in the slot.
+#[derive(Component)]
+pub struct HotkeyLabel(pub ItemSlot);
+
+/// Shows the amount of the price of the item in the slot.
+#[derive(Component)]
+pub struct CostLabel(pub ItemSlot);
+
+/// Shows the currency of the price of the item in the slot.
+#[derive(Component)]
+pub struct CostIcon(pub ItemSlot);
+
 #[derive(Component)]
 pub struct ItemPlacementGhost;
 
+/// Side of the tile the placed item gets attached to.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Side {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Side {
+    /// All sides in the clockwise order.
+    pub const ALL: [Side; 4] = [Side::North, Side::East, Side::South, Side::West];
+
+    /// Index of the neighbor on this side as returned by `MapQuery::get_tile_neighbors`.
+    pub fn neighbor_index(self) -> usize {
+        match self {
+            Side::North => 0,
+            Side::South => 1,
+            Side::West => 2,
+            Side::East => 3,
+        }
+    }
+
+    /// `(flip_x, flip_y, flip_d)` of a cannon base tile attached to the neighbor on this side.
+    pub fn tile_flips(self) -> (bool, bool, bool) {
+        match self {
+            Side::North => (false, false, true),
+            Side::South => (false, true, true),
+            Side::West => (false, false, false),
+            Side::East => (true, false, false),
+        }
+    }
+
+    /// Rotation of an item sprite pointing away from the neighbor on this side.
+    pub fn facing_rotation(self) -> Quat {
+        match self {
+            Side::North => Quat::from_rotation_z(-PI / 2.0),
+            Side::South => Quat::from_rotation_z(PI / 2.0),
+            Side::West => Quat::IDENTITY,
+            Side::East => Quat::from_rotation_z(PI),
+        }
+    }
+
+    /// Picks the orientation after rotating `steps` times clockwise (or counter-clockwise if negative),
+    /// skipping the sides which are not `valid`.
+    ///
+    /// If `self` is not valid, the next valid side clockwise is used as a starting point.
+    pub fn rotate_among(self, valid: [bool; 4], steps: i32) -> Option<Side> {
+        let is_valid = |index: usize| valid[Side::ALL[index].neighbor_index()];
+
+        let start = Side::ALL.iter().position(|dir| *dir == self).unwrap();
+        let mut index = (0..4).map(|offset| (start + offset) % 4).find(|index| is_valid(*index))?;
+
+        for _ in 0..steps.abs() {
+            loop {
+                index = if steps > 0 { (index + 1) % 4 } else { (index + 3) % 4 };
+
+                if is_valid(index) {
+                    break;
+                }
+            }
+        }
+
+        Some(Side::ALL[index])
+    }
+}
+
+/// Orientation chosen by the player for the item being placed.
+#[derive(Component)]
+pub struct GhostOrientation(pub Side);
+
+/// Why the item can't be placed where the ghost currently is, if it can't.
+#[derive(Component)]
+pub struct PlacementFeedback(pub Option<PlacementError>);
+
+#[derive(Component)]
+pub struct PlacementMessage;
+
+/// Highlights the tile under the [`GamepadCursor`](crate::gameplay::resources::GamepadCursor).
+#[derive(Component)]
+pub struct GamepadCursorHighlight;
+
 #[derive(Component)]
 pub struct Cannon {
     pub species: Species,
@@ -77,3 +181,49 @@ pub struct Bullet {
     pub species: Species,
     pub velocity: Vec2,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Valid sides, indexed like [`Side::neighbor_index`].
+    fn valid(sides: &[Side]) -> [bool; 4] {
+        let mut valid = [false; 4];
+        for side in sides {
+            valid[side.neighbor_index()] = true;
+        }
+        valid
+    }
+
+    #[test]
+    fn rotates_through_all_sides() {
+        let all = valid(&Side::ALL);
+
+        assert_eq!(Side::North.rotate_among(all, 1), Some(Side::East));
+        assert_eq!(Side::North.rotate_among(all, -1), Some(Side::West));
+        assert_eq!(Side::West.rotate_among(all, 2), Some(Side::East));
+        assert_eq!(Side::South.rotate_among(all, 4), Some(Side::South));
+    }
+
+    #[test]
+    fn skips_invalid_sides() {
+        let south_west = valid(&[Side::South, Side::West]);
+
+        assert_eq!(Side::South.rotate_among(south_west, 1), Some(Side::West));
+        assert_eq!(Side::West.rotate_among(south_west, 1), Some(Side::South));
+        assert_eq!(Side::South.rotate_among(south_west, -1), Some(Side::West));
+    }
+
+    #[test]
+    fn starts_from_the_next_valid_side_clockwise() {
+        let south_west = valid(&[Side::South, Side::West]);
+
+        assert_eq!(Side::North.rotate_among(south_west, 0), Some(Side::South));
+        assert_eq!(Side::East.rotate_among(south_west, 1), Some(Side::West));
+    }
+
+    #[test]
+    fn no_valid_side() {
+        assert_eq!(Side::North.rotate_among([false; 4], 1), None);
+    }
+}
@@ -0,0 +1,185 @@
+use std::time::Duration;
+use bevy::math::vec3;
+use bevy::prelude::*;
+use bevy_tweening::{Animator, EaseFunction, Tween, TweenCompleted, TweeningType};
+use bevy_tweening::lens::{SpriteColorLens, TextColorLens, TransformPositionLens, TransformScaleLens};
+use crate::{GameState, PreloadedAssets};
+use crate::cleanup::StateScoped;
+use crate::gameplay::components::Monster;
+use crate::gameplay::events::{MonsterHit, MonsterKilled};
+use crate::gameplay::species::SpeciesTable;
+use crate::settings::Settings;
+
+/// `user_data` of tweens whose entity is despawned once they complete.
+pub const DESPAWN_WHEN_DONE: u64 = 1;
+
+const POPUP_DURATION: Duration = Duration::from_millis(800);
+const POPUP_RISE: f32 = 16.0;
+
+const POP_IN_DURATION: Duration = Duration::from_millis(250);
+const PLACEMENT_FLASH_DURATION: Duration = Duration::from_millis(300);
+const DEATH_DURATION: Duration = Duration::from_millis(300);
+const RECOIL_DURATION: Duration = Duration::from_millis(150);
+/// How far a cannon head is pushed back when it fires, in world units.
+const RECOIL_DISTANCE: f32 = 3.0;
+
+/// Grows a freshly placed sprite from nothing, slightly overshooting its size.
+pub fn pop_in() -> Animator<Transform> {
+    Animator::new(Tween::new(
+        EaseFunction::BackOut,
+        TweeningType::Once,
+        POP_IN_DURATION,
+        TransformScaleLens {
+            start: Vec3::ZERO,
+            end: Vec3::ONE,
+        },
+    ))
+}
+
+/// Pushes a cannon head back from where it aims, then eases it back to `rest`.
+pub fn recoil(rest: Vec3, rotation: Quat) -> Animator<Transform> {
+    let kick = rotation * -Vec3::X * RECOIL_DISTANCE;
+
+    Animator::new(Tween::new(
+        EaseFunction::QuadraticOut,
+        TweeningType::Once,
+        RECOIL_DURATION,
+        TransformPositionLens {
+            start: rest + kick,
+            end: rest,
+        },
+    ))
+}
+
+/// Tilemap tiles can't be animated on their own, so a fading square grows out of the new tile instead.
+pub fn spawn_placement_flash(commands: &mut Commands, position: Vec2, tile_size: Vec2) {
+    let grow = Tween::new(
+        EaseFunction::QuadraticOut,
+        TweeningType::Once,
+        PLACEMENT_FLASH_DURATION,
+        TransformScaleLens {
+            start: Vec3::ONE,
+            end: Vec3::splat(1.6),
+        },
+    ).with_completed_event(true, DESPAWN_WHEN_DONE);
+
+    let fade = Tween::new(
+        EaseFunction::QuadraticIn,
+        TweeningType::Once,
+        PLACEMENT_FLASH_DURATION,
+        SpriteColorLens {
+            start: Color::rgba(1.0, 1.0, 1.0, 0.6),
+            end: Color::rgba(1.0, 1.0, 1.0, 0.0),
+        },
+    );
+
+    commands.spawn_bundle(SpriteBundle {
+        sprite: Sprite {
+            color: Color::rgba(1.0, 1.0, 1.0, 0.6),
+            custom_size: Some(tile_size),
+            ..default()
+        },
+        transform: Transform::from_translation(position.extend(0.25)),
+        ..default()
+    }).insert(Animator::new(grow))
+        .insert(Animator::new(fade))
+        .insert(StateScoped(GameState::Gameplay));
+}
+
+/// Shrinks and fades a killed monster, then despawns it.
+pub fn die(commands: &mut Commands, monster: Entity) {
+    let shrink = Tween::new(
+        EaseFunction::QuadraticIn,
+        TweeningType::Once,
+        DEATH_DURATION,
+        TransformScaleLens {
+            start: Vec3::ONE,
+            end: Vec3::ZERO,
+        },
+    ).with_completed_event(true, DESPAWN_WHEN_DONE);
+
+    let fade = Tween::new(
+        EaseFunction::QuadraticIn,
+        TweeningType::Once,
+        DEATH_DURATION,
+        SpriteColorLens {
+            start: Color::WHITE,
+            end: Color::rgba(1.0, 1.0, 1.0, 0.0),
+        },
+    );
+
+    // Without the `Monster` component it is no longer moved, targeted or hit
+    commands.entity(monster)
+        .remove::<Monster>()
+        .insert(Animator::new(shrink))
+        .insert(Animator::new(fade));
+}
+
+pub fn floating_text(mut commands: Commands,
+                     mut hits: EventReader<MonsterHit>,
+                     mut kills: EventReader<MonsterKilled>,
+                     settings: Res<Settings>,
+                     species_table: Res<SpeciesTable>,
+                     pre_assets: Res<PreloadedAssets>)
+{
+    let hits = hits.iter().map(|hit| (hit.damage.to_string(), hit.species, hit.position));
+    let kills = kills.iter().map(|kill| (format!("+{}", kill.bounty), kill.species, kill.position));
+    let popups: Vec<_> = hits.chain(kills).collect();
+
+    if !settings.floating_text {
+        return;
+    }
+
+    for (value, species, position) in popups {
+        let color = species_table.get(species).color;
+        let start = position.extend(50.0);
+
+        let rise = Tween::new(
+            EaseFunction::QuadraticOut,
+            TweeningType::Once,
+            POPUP_DURATION,
+            TransformPositionLens {
+                start,
+                end: start + vec3(0.0, POPUP_RISE, 0.0),
+            },
+        ).with_completed_event(true, DESPAWN_WHEN_DONE);
+
+        let fade = Tween::new(
+            EaseFunction::QuadraticIn,
+            TweeningType::Once,
+            POPUP_DURATION,
+            TextColorLens {
+                start: color,
+                end: Color::rgba(color.r(), color.g(), color.b(), 0.0),
+                section: 0,
+            },
+        );
+
+        commands.spawn_bundle(Text2dBundle {
+            text: Text::with_section(value, TextStyle {
+                font: pre_assets.font.clone(),
+                font_size: 56.0,
+                color,
+            }, TextAlignment {
+                horizontal: HorizontalAlign::Center,
+                vertical: VerticalAlign::Center,
+            }),
+            transform: Transform {
+                translation: start,
+                scale: vec3(0.25, 0.25, 1.0),
+                ..default()
+            },
+            ..default()
+        }).insert(Animator::new(rise))
+            .insert(Animator::new(fade))
+            .insert(StateScoped(GameState::Gameplay));
+    }
+}
+
+pub fn despawn_finished_tweens(mut commands: Commands, mut completed: EventReader<TweenCompleted>) {
+    for event in completed.iter() {
+        if event.user_data == DESPAWN_WHEN_DONE {
+            commands.entity(event.entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use bevy::prelude::*;
+use crate::gameplay::components::Species;
+
+/// A bullet hit a monster.
+pub struct MonsterHit {
+    pub species: Species,
+    pub damage: i32,
+    pub position: Vec2,
+}
+
+/// A monster was killed and its bounty added to the wallet.
+pub struct MonsterKilled {
+    pub species: Species,
+    pub bounty: u32,
+    pub max_health: i32,
+    pub position: Vec2,
+}
+
+/// A cannon shot a bullet.
+pub struct CannonFired {
+    pub species: Species,
+}
+
+/// The player placed an item on the map.
+pub struct ItemPlaced;
+
+/// The player tried to place an item where it can't go.
+pub struct PlacementDenied;
+
+/// A monster reached the core and the game is lost.
+pub struct CoreDestroyed;
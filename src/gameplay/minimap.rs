@@ -0,0 +1,175 @@
+use bevy::math::vec2;
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use bevy_ecs_tilemap::{Tile, TilePos};
+use crate::{palette, GameState};
+use crate::cleanup::StateScoped;
+use crate::gameplay::{TILE_CONNECTOR, TILE_CORE};
+use crate::gameplay::components::*;
+use crate::gameplay::pointer::MapGeometry;
+use crate::gameplay::species::SpeciesTable;
+
+/// Size of a single map tile on the minimap, in screen pixels.
+const MINIMAP_TILE_SIZE: f32 = 4.0;
+const MONSTER_DOT_SIZE: f32 = 3.0;
+
+#[derive(Component)]
+pub struct Minimap;
+
+/// A dot or a line drawn on the minimap. These are reused between frames, and hidden while not needed.
+#[derive(Component)]
+pub struct MinimapMarker;
+
+pub fn spawn_minimap(commands: &mut Commands) {
+    commands.spawn_bundle(ButtonBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                right: Val::Px(12.0),
+                top: Val::Px(12.0),
+                ..default()
+            },
+            ..default()
+        },
+        color: Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+        ..default()
+    }).insert(Minimap)
+        .insert(StateScoped(GameState::Gameplay));
+}
+
+pub fn update_minimap(mut commands: Commands,
+                      mut minimap: Query<(Entity, &mut Style), With<Minimap>>,
+                      mut markers: Query<(Entity, &mut Style, &mut UiColor), (With<MinimapMarker>, Without<Minimap>)>,
+                      tiles: Query<(&TilePos, &Tile)>,
+                      cannons: Query<(&GlobalTransform, &Cannon)>,
+                      monsters: Query<(&GlobalTransform, &Monster)>,
+                      camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+                      geometry: Res<MapGeometry>,
+                      species_table: Res<SpeciesTable>,
+                      windows: Res<Windows>)
+{
+    let (minimap_entity, mut minimap_style) = minimap.single_mut();
+
+    let minimap_size = geometry.size.as_vec2() * MINIMAP_TILE_SIZE;
+    let size = Size::new(Val::Px(minimap_size.x), Val::Px(minimap_size.y));
+    // Changing a style lays out the whole UI again, so they are only touched when different
+    if minimap_style.size != size {
+        minimap_style.size = size;
+    }
+
+    let (map_min, _) = geometry.bounds();
+    let to_minimap = |world_pos: Vec2| (world_pos - map_min) / geometry.tile_size * MINIMAP_TILE_SIZE;
+    let tile_size = Vec2::splat(MINIMAP_TILE_SIZE);
+
+    // Bottom left corner, size and colour of each marker
+    let mut wanted: Vec<(Vec2, Vec2, Color)> = Vec::new();
+
+    for (tile_pos, tile) in tiles.iter() {
+        let color = match tile.texture_index {
+            TILE_CORE => palette::WHITE,
+            TILE_CONNECTOR => Color::rgb(0.5, 0.5, 0.5),
+            _ => continue,
+        };
+
+        let corner = vec2(tile_pos.0 as f32, tile_pos.1 as f32) * MINIMAP_TILE_SIZE;
+        wanted.push((corner, tile_size, color));
+    }
+
+    for (transform, cannon) in cannons.iter() {
+        if let Some(tile_pos) = geometry.tile_at(transform.translation.truncate()) {
+            let corner = vec2(tile_pos.0 as f32, tile_pos.1 as f32) * MINIMAP_TILE_SIZE;
+            wanted.push((corner, tile_size, species_table.get(cannon.species).color));
+        }
+    }
+
+    for (transform, monster) in monsters.iter() {
+        let center = to_minimap(transform.translation.truncate());
+        let dot_size = Vec2::splat(MONSTER_DOT_SIZE);
+        wanted.push((center - dot_size / 2.0, dot_size, species_table.get(monster.0).color));
+    }
+
+    if let (Ok((camera_transform, projection)), Some(window)) = (camera.get_single(), windows.get_primary()) {
+        let view_size = vec2(window.width(), window.height()) * projection.scale;
+        let center = camera_transform.translation.truncate();
+
+        let min = to_minimap(center - view_size / 2.0).max(Vec2::ZERO);
+        let max = to_minimap(center + view_size / 2.0).min(minimap_size);
+        let size = max - min;
+
+        wanted.push((min, vec2(size.x, 1.0), palette::WHITE));
+        wanted.push((vec2(min.x, max.y - 1.0), vec2(size.x, 1.0), palette::WHITE));
+        wanted.push((min, vec2(1.0, size.y), palette::WHITE));
+        wanted.push((vec2(max.x - 1.0, min.y), vec2(1.0, size.y), palette::WHITE));
+    }
+
+    let mut existing = markers.iter_mut();
+    for (corner, size, color) in wanted {
+        let style = Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                left: Val::Px(corner.x),
+                bottom: Val::Px(corner.y),
+                ..default()
+            },
+            size: Size::new(Val::Px(size.x), Val::Px(size.y)),
+            ..default()
+        };
+
+        match existing.next() {
+            Some((_, mut marker_style, mut marker_color)) => {
+                if *marker_style != style {
+                    *marker_style = style;
+                }
+                if marker_color.0 != color {
+                    marker_color.0 = color;
+                }
+            },
+            None => {
+                commands.entity(minimap_entity).with_children(|parent| {
+                    parent.spawn_bundle(NodeBundle {
+                        style,
+                        color: color.into(),
+                        // Clicks go through to the minimap underneath
+                        focus_policy: FocusPolicy::Pass,
+                        ..default()
+                    }).insert(MinimapMarker);
+                });
+            },
+        }
+    }
+
+    // Hidden rather than despawned, the UI never lets go of the layout of despawned nodes
+    for (_, mut marker_style, _) in existing {
+        if marker_style.display != Display::None {
+            marker_style.display = Display::None;
+        }
+    }
+}
+
+/// Centers the camera on the clicked point. Holding the button drags the view around.
+pub fn minimap_click(minimap: Query<(&Interaction, &Node, &GlobalTransform), With<Minimap>>,
+                     mut camera: Query<&mut Transform, With<MainCamera>>,
+                     geometry: Res<MapGeometry>,
+                     windows: Res<Windows>)
+{
+    let (interaction, node, minimap_transform) = minimap.single();
+    if *interaction != Interaction::Clicked {
+        return;
+    }
+
+    let cursor = match windows.get_primary().and_then(|window| window.cursor_position()) {
+        Some(cursor) => cursor,
+        None => return,
+    };
+
+    // UI nodes are positioned by their center
+    let corner = minimap_transform.translation.truncate() - node.size / 2.0;
+    let fraction = ((cursor - corner) / node.size).clamp(Vec2::ZERO, Vec2::ONE);
+
+    let (map_min, map_max) = geometry.bounds();
+    let target = map_min + (map_max - map_min) * fraction;
+
+    let mut camera_transform = camera.single_mut();
+    camera_transform.translation.x = target.x;
+    camera_transform.translation.y = target.y;
+}
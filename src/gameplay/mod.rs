@@ -6,34 +6,116 @@ use bevy::ui::FocusPolicy;
 use bevy_ecs_tilemap::prelude::*;
 use bevy_inspector_egui::InspectorPlugin;
 
-use crate::{GameState, palette, PreloadedAssets};
+use crate::{GameState, PreloadedAssets};
+use crate::cleanup::StateScoped;
+use crate::loading::{asset_path, LoadingFailure};
+use crate::localization::Localization;
+use crate::theme::{spawn_panel, Skin, Theme};
 use crate::assets::*;
+use crate::gameplay::balance::*;
+use crate::gameplay::camera::*;
+use crate::gameplay::pointer::*;
+use crate::gameplay::minimap::*;
+use crate::gameplay::tooltip::*;
+use crate::gameplay::effects::*;
+use crate::gameplay::particles::*;
+use crate::gameplay::shake::*;
+use crate::gameplay::spawn_preview::*;
+use crate::gameplay::events::*;
 use crate::gameplay::components::*;
 use crate::gameplay::resources::*;
+use crate::gameplay::species::*;
 use crate::gameplay::systems::*;
 
-mod components;
+pub mod components;
 mod bundles;
 mod resources;
 mod systems;
+mod camera;
+pub mod pointer;
+mod minimap;
+mod tooltip;
+mod effects;
+mod particles;
+mod shake;
+mod spawn_preview;
+pub mod events;
+pub mod species;
+pub mod balance;
+pub mod placement;
 
 pub const TILE_NONE: u16 = 0;
 pub const TILE_CORE: u16 = 1;
 pub const TILE_CONNECTOR: u16 = 2;
 pub const TILE_CANNON: u16 = 3;
 
+/// Systems other systems in the same frame depend on.
+#[derive(SystemLabel, Clone, Hash, Debug, PartialEq, Eq)]
+pub enum GameplaySystem {
+    /// Moves the gamepad cursor.
+    Cursor,
+    /// Takes last frame's screen shake off the main camera.
+    ShakeReset,
+    /// Pans and zooms the main camera.
+    ///
+    /// `Camera` and `GlobalTransform` only catch up at the end of the frame, so anything converting between the
+    /// screen and the world in the same frame has to run after this and use the camera's `Transform` and
+    /// `OrthographicProjection`, like [`update_pointer`] does.
+    Camera,
+    /// Updates the [`Pointer`] and [`MapGeometry`].
+    Pointer,
+}
+
 pub fn register_systems(app: &mut App) {
+    app.add_asset::<SpeciesTable>()
+        .init_asset_loader::<SpeciesTableLoader>()
+        .add_asset::<Balance>()
+        .init_asset_loader::<BalanceLoader>()
+        .add_event::<MonsterHit>()
+        .add_event::<MonsterKilled>()
+        .add_event::<CannonFired>()
+        .add_event::<ItemPlaced>()
+        .add_event::<PlacementDenied>()
+        .add_event::<CoreDestroyed>();
+
     app.add_system_set(SystemSet::on_enter(GameState::Gameplay).with_system(gameplay_enter));
+    app.add_system_set(SystemSet::on_exit(GameState::Gameplay).with_system(gameplay_exit));
 
     app.add_system_set(SystemSet::on_update(GameState::Gameplay)
         .with_system(core_spinner)
         .with_system(wallet_display)
         .with_system(buy_item)
-        .with_system(drag_ghost)
-        .with_system(update_cannons)
+        .with_system(item_hotkeys)
+        .with_system(hotkey_labels)
+        .with_system(cost_labels)
+        .with_system(move_gamepad_cursor.label(GameplaySystem::Cursor))
+        .with_system(reset_camera_shake.label(GameplaySystem::ShakeReset))
+        .with_system(pan_camera.label(GameplaySystem::Camera).after(GameplaySystem::Cursor).after(GameplaySystem::ShakeReset))
+        .with_system(zoom_camera.label(GameplaySystem::Camera).after(pan_camera))
+        .with_system(update_pointer.label(GameplaySystem::Pointer).after(GameplaySystem::Camera))
+        .with_system(drag_ghost.after(GameplaySystem::Pointer))
+        .with_system(minimap_click.after(GameplaySystem::ShakeReset).before(GameplaySystem::Camera))
+        .with_system(update_minimap.after(GameplaySystem::Camera))
+        .with_system(placement_message.after(GameplaySystem::Pointer))
+        .with_system(show_tooltip.after(GameplaySystem::Pointer))
+        .with_system(undo_build)
+        // Recoil gets inserted on the cannon heads, which undoing a build despawns
+        .with_system(update_cannons.before(undo_build))
         .with_system(spawn_monsters)
+        .with_system(update_spawn_preview)
         .with_system(move_monsters)
-        .with_system(move_bullets));
+        .with_system(count_down_defeat)
+        .with_system(move_bullets)
+        .with_system(update_health_bars)
+        .with_system(floating_text)
+        .with_system(emit_particles)
+        .with_system(update_particles)
+        .with_system(shake_on_impact)
+        .with_system(apply_camera_shake.after(GameplaySystem::Pointer))
+        .with_system(reload_balance));
+
+    // Effects keep playing while paused, so they have to be cleaned up in every state
+    app.add_system(despawn_finished_tweens);
 
     #[cfg(debug_assertions)]
     app.add_plugin(InspectorPlugin::<Wallet>::new());
@@ -41,24 +123,65 @@ pub fn register_systems(app: &mut App) {
 
 pub fn gameplay_enter(mut commands: Commands,
                       game_assets: Res<GameplayAssets>,
-                      ui_assets: Res<UIAssets>,
+                      species_tables: Res<Assets<SpeciesTable>>,
+                      balances: Res<Assets<Balance>>,
                       pre_assets: Res<PreloadedAssets>,
+                      theme: Res<Theme>,
+                      localization: Res<Localization>,
+                      asset_server: Res<AssetServer>,
+                      mut state: ResMut<State<GameState>>,
                       mut map_query: MapQuery) {
-    commands.insert_resource(Wallet {
-        red_squares: 20,
-        green_triangles: 20,
-        blue_circles: 20,
-    });
+    let species = match species_tables.get(&game_assets.species) {
+        Some(species) => species.clone(),
+        None => {
+            // Only possible if it was unloaded again since the loading screen
+            fail_loading(&mut commands, &mut state, asset_path(&asset_server, &game_assets.species), None);
+            return;
+        },
+    };
+
+    let balance = match balances.get(&game_assets.balance) {
+        Some(balance) => balance.clone(),
+        None => {
+            fail_loading(&mut commands, &mut state, asset_path(&asset_server, &game_assets.balance), None);
+            return;
+        },
+    };
+
+    // A balance file which parses can still miss values, the failure screen tells which
+    if let Err(err) = balance.validate(&species) {
+        fail_loading(&mut commands, &mut state, asset_path(&asset_server, &game_assets.balance), Some(err.to_string()));
+        return;
+    }
+
+    commands.insert_resource(Wallet::new(species.len(), balance.start_money));
+
+    commands.insert_resource(SpawnSchedule::new(&balance, species.len()));
 
-    commands.insert_resource(MonsterSpawnCooldown(10.0, DEFAULT_HEALTH));
+    commands.insert_resource(BuildHistory::default());
+
+    commands.insert_resource(GamepadCursor::default());
+
+    commands.insert_resource(MapGeometry::default());
+
+    commands.insert_resource(Pointer::default());
+
+    commands.insert_resource(CameraShake::default());
+
+    commands.insert_resource(HitStop::default());
+
+    commands.insert_resource(DefeatCountdown::default());
 
     let mut world_camera = OrthographicCameraBundle::new_2d();
-    world_camera.orthographic_projection.scale = 1.0 / 3.0;
+    world_camera.orthographic_projection.scale = 1.0 / DEFAULT_ZOOM;
     commands.spawn_bundle(world_camera)
-        .insert(MainCamera);
+        .insert(MainCamera)
+        .insert(StateScoped(GameState::Gameplay));
 
-    commands.spawn_bundle(UiCameraBundle::default());
+    commands.spawn_bundle(UiCameraBundle::default())
+        .insert(StateScoped(GameState::Gameplay));
 
+    // Not state scoped, the tiles aren't children of anything and only the map query knows about them
     let map_entity = commands.spawn().id();
     let mut map = Map::new(0, map_entity);
 
@@ -109,7 +232,54 @@ pub fn gameplay_enter(mut commands: Commands,
         transform: Transform::from_xyz(0.0, 0.0, 0.2),
         ..Default::default()
     }).insert(CoreSpinner)
-        .insert(Health(DEFAULT_HEALTH));
+        .insert(StateScoped(GameState::Gameplay));
+
+    commands.spawn_bundle(SpriteBundle {
+        sprite: Sprite {
+            color: Color::rgba(1.0, 1.0, 1.0, 0.25),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 0.0, 99.0),
+        visibility: Visibility { is_visible: false },
+        ..default()
+    }).insert(GamepadCursorHighlight)
+        .insert(StateScoped(GameState::Gameplay));
+
+    let wallet_content = commands.spawn_bundle(NodeBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_grow: 1.0,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }).with_children(|panel| {
+        for kind in species.iter() {
+            insert_wallet_info(panel, kind, &species, &pre_assets, &localization);
+        }
+    }).id();
+    let wallet_panel = spawn_panel(&mut commands, &theme, Skin::Panel, default(), wallet_content);
+
+    let buy_content = commands.spawn_bundle(NodeBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_grow: 1.0,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }).with_children(|panel| {
+        let mut items = vec![Item::Connector];
+        items.extend(species.iter().map(Item::Cannon));
+
+        for (slot, item) in items.into_iter().enumerate() {
+            let cost = balance.item_cost(&species, item).unwrap();
+            insert_cost_info(panel, cost, item, ItemSlot(slot), &game_assets, &species, &pre_assets, &theme);
+        }
+    }).id();
+    let buy_panel = spawn_panel(&mut commands, &theme, Skin::Panel, default(), buy_content);
 
     commands.spawn_bundle(NodeBundle {
         style: Style {
@@ -121,69 +291,56 @@ pub fn gameplay_enter(mut commands: Commands,
         },
         color: Color::NONE.into(),
         ..default()
-    }).with_children(|parent| {
-        parent.spawn_bundle(NodeBundle {
-            style: Style {
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::Center,
-                ..default()
-            },
-            color: Color::NONE.into(),
-            ..default()
-        }).with_children(|panel| {
-            insert_wallet_info(panel, Species::Red, &ui_assets, &pre_assets);
-            insert_wallet_info(panel, Species::Green, &ui_assets, &pre_assets);
-            insert_wallet_info(panel, Species::Blue, &ui_assets, &pre_assets);
-        });
+    }).insert(StateScoped(GameState::Gameplay))
+        .push_children(&[wallet_panel, buy_panel]);
 
-        parent.spawn_bundle(NodeBundle {
-            style: Style {
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::Center,
-                ..default()
-            },
-            color: Color::NONE.into(),
+    spawn_minimap(&mut commands);
+
+    spawn_tooltip(&mut commands, &theme);
+
+    spawn_preview_panel(&mut commands, &pre_assets, &theme);
+
+    commands.spawn_bundle(TextBundle {
+        text: Text::with_section("", TextStyle {
+            font: pre_assets.font.clone(),
+            font_size: 56.0,
+            color: theme.text,
+        }, TextAlignment::default()),
+        transform: Transform {
+            scale: vec3(0.25, 0.25, 1.0) * 2.0,
             ..default()
-        }).with_children(|panel| {
-            insert_cost_info(panel, Money::new(Species::Red, 5), Item::Connector, &game_assets, &pre_assets, &ui_assets);
-
-            insert_cost_info(panel, Money::new(Species::Red, 10), Item::RedCannon, &game_assets, &pre_assets, &ui_assets);
-            insert_cost_info(panel, Money::new(Species::Green, 10), Item::GreenCannon, &game_assets, &pre_assets, &ui_assets);
-            insert_cost_info(panel, Money::new(Species::Blue, 10), Item::BlueCannon, &game_assets, &pre_assets, &ui_assets);
-
-            /*insert_cost_info(panel, Money::new(Species::Red, 10), Item::ConverterRedGreen, &game_assets, &pre_assets, &ui_assets);
-            insert_cost_info(panel, Money::new(Species::Red, 10), Item::ConverterRedBlue, &game_assets, &pre_assets, &ui_assets);
-            insert_cost_info(panel, Money::new(Species::Green, 10), Item::ConverterGreenRed, &game_assets, &pre_assets, &ui_assets);
-            insert_cost_info(panel, Money::new(Species::Green, 10), Item::ConverterGreenBlue, &game_assets, &pre_assets, &ui_assets);
-            insert_cost_info(panel, Money::new(Species::Blue, 10), Item::ConverterBlueRed, &game_assets, &pre_assets, &ui_assets);
-            insert_cost_info(panel, Money::new(Species::Blue, 10), Item::ConverterBlueGreen, &game_assets, &pre_assets, &ui_assets);*/
-        });
-    });
+        },
+        style: Style {
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        visibility: Visibility { is_visible: false },
+        ..default()
+    }).insert(PlacementMessage)
+        .insert(StateScoped(GameState::Gameplay));
+
+    commands.insert_resource(species);
+    commands.insert_resource(balance);
 }
 
-fn insert_wallet_info(parent: &mut ChildBuilder, species: Species, ui_assets: &UIAssets, pre_assets: &PreloadedAssets) {
-    fn color(species: Species) -> Color {
-        match species {
-            Species::Red => palette::RED,
-            Species::Green => palette::GREEN,
-            Species::Blue => palette::BLUE,
-        }
-    }
+pub fn gameplay_exit(mut commands: Commands, mut map_query: MapQuery) {
+    map_query.despawn(&mut commands, 0u16);
+}
 
-    let image = |species: Species| -> Handle<Image> {
-        match species {
-            Species::Red => ui_assets.red_square.clone(),
-            Species::Green => ui_assets.green_triangle.clone(),
-            Species::Blue => ui_assets.blue_circle.clone(),
-        }
-    };
+fn fail_loading(commands: &mut Commands, state: &mut State<GameState>, path: String, reason: Option<String>) {
+    commands.insert_resource(LoadingFailure { path, reason });
+    state.set(GameState::LoadingFailed).unwrap();
+}
+
+fn insert_wallet_info(parent: &mut ChildBuilder, species: Species, species_table: &SpeciesTable, pre_assets: &PreloadedAssets, localization: &Localization) {
+    let info = species_table.get(species);
 
     parent.spawn_bundle(NodeBundle {
-        color: palette::BLACK.into(),
+        color: Color::NONE.into(),
         ..default()
     }).with_children(|section| {
         section.spawn_bundle(NodeBundle {
-            image: UiImage(image(species)),
+            image: UiImage(info.currency_icon.clone()),
             style: Style {
                 size: Size::new(Val::Px(24.0 * 3.0), Val::Px(24.0 * 3.0)),
                 ..default()
@@ -191,10 +348,10 @@ fn insert_wallet_info(parent: &mut ChildBuilder, species: Species, ui_assets: &U
             ..default()
         });
         section.spawn_bundle(TextBundle {
-            text: Text::with_section("???", TextStyle {
+            text: Text::with_section(localization.get("wallet.unknown"), TextStyle {
                 font: pre_assets.font.clone(),
                 font_size: 56.0,
-                color: color(species),
+                color: info.color,
             }, TextAlignment::default()),
             transform: Transform {
                 scale: vec3(0.25, 0.25, 1.0) * 3.0,
@@ -221,37 +378,20 @@ fn insert_wallet_info(parent: &mut ChildBuilder, species: Species, ui_assets: &U
 fn insert_cost_info(parent: &mut ChildBuilder,
                     cost: Money,
                     item: Item,
+                    slot: ItemSlot,
                     game_assets: &GameplayAssets,
+                    species_table: &SpeciesTable,
                     pre_assets: &PreloadedAssets,
-                    ui_assets: &UIAssets) {
-
-    let money_color = match cost.species {
-        Species::Red => palette::RED,
-        Species::Green => palette::GREEN,
-        Species::Blue => palette::BLUE,
-    };
+                    theme: &Theme) {
 
-    let money_image = match cost.species {
-        Species::Red => (ui_assets.red_square_small.clone(), vec2(5.0, 5.0)),
-        Species::Green => (ui_assets.green_triangle_small.clone(), vec2(7.0, 4.0)),
-        Species::Blue => (ui_assets.blue_circle_small.clone(), vec2(6.0, 6.0)),
-    };
+    let money_info = species_table.get(cost.species);
+    let money_color = money_info.color;
+    let money_image = (money_info.currency_icon_small.clone(), money_info.currency_icon_small_size);
 
-    let item_image = match item {
-        Item::Connector => game_assets.connector.clone(),
-        Item::RedCannon => game_assets.cannon_red.clone(),
-        Item::GreenCannon => game_assets.cannon_green.clone(),
-        Item::BlueCannon => game_assets.cannon_blue.clone(),
-        Item::ConverterRedGreen => game_assets.converter_red_green.clone(),
-        Item::ConverterRedBlue => game_assets.converter_red_blue.clone(),
-        Item::ConverterGreenRed => game_assets.converter_green_red.clone(),
-        Item::ConverterGreenBlue => game_assets.converter_green_blue.clone(),
-        Item::ConverterBlueRed => game_assets.converter_blue_red.clone(),
-        Item::ConverterBlueGreen => game_assets.converter_blue_green.clone(),
-    };
+    let item_image = item_image(item, game_assets, species_table);
 
     parent.spawn_bundle(ButtonBundle {
-        color: palette::BLACK.into(),
+        color: Color::NONE.into(),
         style: Style {
             flex_direction: FlexDirection::ColumnReverse,
             align_items: AlignItems::Center,
@@ -292,7 +432,7 @@ fn insert_cost_info(parent: &mut ChildBuilder,
                     ..default()
                 },
                 ..default()
-            });
+            }).insert(CostIcon(slot));
 
             line.spawn_bundle(TextBundle {
                 text: Text::with_section(cost.amount.to_string(), TextStyle {
@@ -316,10 +456,41 @@ fn insert_cost_info(parent: &mut ChildBuilder,
                     ..default()
                 },
                 ..default()
-            });
+            }).insert(CostLabel(slot));
         });
+
+        section.spawn_bundle(TextBundle {
+            text: Text::with_section("", TextStyle {
+                font: pre_assets.font.clone(),
+                font_size: 56.0,
+                color: theme.text,
+            }, default()),
+            transform: Transform {
+                scale: vec3(0.25, 0.25, 1.0) * 2.0,
+                ..default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(3.0),
+                    top: Val::Px(3.0),
+                    ..default()
+                },
+                ..default()
+            },
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        }).insert(HotkeyLabel(slot));
     }).insert(BuyItemButton {
         cost,
         item,
-    });
+    }).insert(slot);
+}
+
+pub fn item_image(item: Item, game_assets: &GameplayAssets, species_table: &SpeciesTable) -> Handle<Image> {
+    match item {
+        Item::Connector => game_assets.connector.clone(),
+        Item::Cannon(species) => species_table.get(species).cannon.clone(),
+        Item::Converter { from, to } => species_table.get(from).converters[&to].clone(),
+    }
 }
\ No newline at end of file
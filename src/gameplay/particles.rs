@@ -0,0 +1,104 @@
+use std::f32::consts::PI;
+use bevy::prelude::*;
+use rand::random;
+use crate::GameState;
+use crate::cleanup::StateScoped;
+use crate::gameplay::components::Species;
+use crate::gameplay::events::{MonsterHit, MonsterKilled};
+use crate::gameplay::species::SpeciesTable;
+
+/// Particles alive at once. Bursts over the cap are cut short rather than slowing the game down.
+const MAX_PARTICLES: usize = 400;
+
+const HIT_PARTICLES: usize = 4;
+const KILL_PARTICLES: usize = 14;
+/// Initial speed range of the particles, in world units per second.
+const MIN_SPEED: f32 = 30.0;
+const MAX_SPEED: f32 = 90.0;
+/// Fraction of the velocity lost every second.
+const DRAG: f32 = 3.0;
+/// Lifetime range, in seconds.
+const MIN_LIFETIME: f32 = 0.3;
+const MAX_LIFETIME: f32 = 0.6;
+
+/// A small shape flying away from a hit or a dead monster.
+#[derive(Component)]
+pub struct Particle {
+    velocity: Vec2,
+    age: f32,
+    lifetime: f32,
+    spin: f32,
+}
+
+/// Emits a burst of currency shapes in the species colour for every hit and kill.
+pub fn emit_particles(mut commands: Commands,
+                      mut hits: EventReader<MonsterHit>,
+                      mut kills: EventReader<MonsterKilled>,
+                      particles: Query<(), With<Particle>>,
+                      species_table: Res<SpeciesTable>)
+{
+    let hits = hits.iter().map(|hit| (hit.species, hit.position, HIT_PARTICLES));
+    let kills = kills.iter().map(|kill| (kill.species, kill.position, KILL_PARTICLES));
+
+    let mut budget = MAX_PARTICLES.saturating_sub(particles.iter().count());
+
+    for (species, position, count) in hits.chain(kills) {
+        let count = count.min(budget);
+        budget -= count;
+
+        for _ in 0..count {
+            spawn_particle(&mut commands, species, position, &species_table);
+        }
+    }
+}
+
+fn spawn_particle(commands: &mut Commands, species: Species, position: Vec2, species_table: &SpeciesTable) {
+    let info = species_table.get(species);
+
+    let angle = random::<f32>() * PI * 2.0;
+    let speed = MIN_SPEED + random::<f32>() * (MAX_SPEED - MIN_SPEED);
+
+    commands.spawn_bundle(SpriteBundle {
+        texture: info.currency_icon_small.clone(),
+        sprite: Sprite {
+            custom_size: Some(info.currency_icon_small_size),
+            ..default()
+        },
+        transform: Transform {
+            translation: position.extend(40.0),
+            rotation: Quat::from_rotation_z(angle),
+            ..default()
+        },
+        ..default()
+    }).insert(Particle {
+        velocity: Vec2::new(angle.cos(), angle.sin()) * speed,
+        age: 0.0,
+        lifetime: MIN_LIFETIME + random::<f32>() * (MAX_LIFETIME - MIN_LIFETIME),
+        spin: (random::<f32>() - 0.5) * 4.0 * PI,
+    }).insert(StateScoped(GameState::Gameplay));
+}
+
+/// Moves, slows down and fades the particles, despawning them at the end of their lifetime.
+pub fn update_particles(mut commands: Commands,
+                        mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+                        time: Res<Time>)
+{
+    let delta = time.delta_seconds();
+
+    for (entity, mut particle, mut transform, mut sprite) in particles.iter_mut() {
+        particle.age += delta;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let velocity = particle.velocity;
+        transform.translation += (velocity * delta).extend(0.0);
+        transform.rotate(Quat::from_rotation_z(particle.spin * delta));
+        particle.velocity = velocity * (1.0 - DRAG * delta).max(0.0);
+
+        let remaining = 1.0 - particle.age / particle.lifetime;
+        sprite.color.set_a(remaining);
+        transform.scale = Vec3::splat(0.5 + remaining * 0.5);
+    }
+}
@@ -0,0 +1,163 @@
+use bevy::math::vec3;
+use bevy::prelude::*;
+use bevy_ecs_tilemap::{MapQuery, MapTileError, Tile, TilePos};
+use crate::GameState;
+use crate::cleanup::StateScoped;
+use crate::gameplay::components::*;
+use crate::gameplay::effects::pop_in;
+use crate::gameplay::resources::Wallet;
+use crate::gameplay::species::SpeciesTable;
+use crate::gameplay::{TILE_CANNON, TILE_CONNECTOR, TILE_CORE, TILE_NONE};
+
+/// Reason why an item can't be placed on a tile.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PlacementError {
+    OutOfBounds,
+    Occupied,
+    BlockedTerrain,
+    NotConnected,
+    CannotAfford,
+}
+
+impl PlacementError {
+    /// Localization key of the message shown next to the cursor.
+    pub fn message_key(self) -> &'static str {
+        match self {
+            PlacementError::OutOfBounds => "placement.out_of_bounds",
+            PlacementError::Occupied => "placement.occupied",
+            PlacementError::BlockedTerrain => "placement.blocked_terrain",
+            PlacementError::NotConnected => "placement.not_connected",
+            PlacementError::CannotAfford => "placement.cannot_afford",
+        }
+    }
+}
+
+/// A tile an item can be placed on.
+pub struct PlacementSite {
+    pub tile_pos: TilePos,
+    /// Whether the neighbor on each side can power the item, indexed by `Side::neighbor_index`.
+    pub connected: [bool; 4],
+}
+
+/// Checks whether an item costing `cost` can be placed on the tile at `tile_pos`.
+pub fn check_placement(tile_pos: TilePos,
+                       cost: Money,
+                       wallet: &Wallet,
+                       map_query: &MapQuery,
+                       tile_query: &Query<&Tile>) -> Result<PlacementSite, PlacementError> {
+    match map_query.get_tile_entity(tile_pos, 0, 0) {
+        Ok(tile_entity) => match tile_query.get(tile_entity).unwrap().texture_index {
+            TILE_NONE => (),
+            TILE_CORE | TILE_CONNECTOR | TILE_CANNON => return Err(PlacementError::Occupied),
+            _ => return Err(PlacementError::BlockedTerrain),
+        },
+        Err(MapTileError::OutOfBounds(_)) => return Err(PlacementError::OutOfBounds),
+        Err(_) => (),
+    }
+
+    let neighbors = map_query.get_tile_neighbors(tile_pos, 0, 0);
+
+    let mut connected = [false; 4];
+    for (i, neighbor) in neighbors.iter().take(4).enumerate() {
+        if let Ok(tile_entity) = neighbor {
+            let tile = tile_query.get(*tile_entity).unwrap();
+
+            connected[i] = matches!(tile.texture_index, TILE_CORE | TILE_CONNECTOR);
+        }
+    }
+
+    if !connected.contains(&true) {
+        return Err(PlacementError::NotConnected);
+    }
+
+    if wallet.get(cost.species) < cost.amount {
+        return Err(PlacementError::CannotAfford);
+    }
+
+    Ok(PlacementSite {
+        tile_pos,
+        connected,
+    })
+}
+
+/// An item placed on the map, with everything needed to remove or place it again.
+#[derive(Copy, Clone)]
+pub struct BuildAction {
+    pub item: Item,
+    pub cost: Money,
+    pub tile_pos: TilePos,
+    pub side: Side,
+    /// World position of the tile center.
+    pub translation: Vec2,
+    /// When the action was last done or undone, in seconds since startup.
+    pub time: f64,
+}
+
+pub fn build(commands: &mut Commands,
+             map_query: &mut MapQuery,
+             species_table: &SpeciesTable,
+             action: &BuildAction) {
+    match action.item {
+        Item::Connector => {
+            let tile = Tile {
+                texture_index: TILE_CONNECTOR,
+                ..default()
+            };
+            map_query.set_tile(commands, action.tile_pos, tile, 0, 0).unwrap();
+        }
+
+        Item::Cannon(species) => {
+            let (flip_x, flip_y, flip_d) = action.side.tile_flips();
+            let tile = Tile {
+                texture_index: TILE_CANNON,
+                flip_x,
+                flip_y,
+                flip_d,
+                ..default()
+            };
+            let cannon_entity = map_query.set_tile(commands, action.tile_pos, tile, 0, 0).unwrap();
+
+            let cannon_head = commands.spawn_bundle(SpriteBundle {
+                texture: species_table.get(species).cannon.clone(),
+                transform: Transform {
+                    translation: vec3(action.translation.x, action.translation.y, 0.3),
+                    rotation: action.side.facing_rotation(),
+                    scale: Vec3::ZERO,
+                },
+                ..default()
+            }).insert(Cannon {
+                species,
+                cooldown: 0.0,
+            }).insert(pop_in())
+                .insert(StateScoped(GameState::Gameplay))
+                .id();
+
+            commands.entity(cannon_entity).insert(CannonBase(cannon_head));
+        }
+
+        // Converters don't do anything on the map yet
+        Item::Converter { .. } => (),
+    }
+
+    map_query.notify_chunk_for_tile(action.tile_pos, 0u16, 0u16);
+}
+
+pub fn demolish(commands: &mut Commands,
+                map_query: &mut MapQuery,
+                cannon_bases: &Query<&CannonBase>,
+                action: &BuildAction) {
+    if let Ok(tile_entity) = map_query.get_tile_entity(action.tile_pos, 0, 0) {
+        if let Ok(cannon_base) = cannon_bases.get(tile_entity) {
+            commands.entity(cannon_base.0).despawn();
+            commands.entity(tile_entity).remove::<CannonBase>();
+        }
+    }
+
+    let tile = Tile {
+        texture_index: TILE_NONE,
+        ..default()
+    };
+    map_query.set_tile(commands, action.tile_pos, tile, 0, 0).unwrap();
+
+    map_query.notify_chunk_for_tile(action.tile_pos, 0u16, 0u16);
+}
@@ -0,0 +1,102 @@
+use bevy::math::vec2;
+use bevy::prelude::*;
+use bevy::render::camera::CameraProjection;
+use bevy_ecs_tilemap::{Map, MapQuery, TilePos};
+use crate::gameplay::components::MainCamera;
+use crate::gameplay::resources::GamepadCursor;
+
+/// Placement of the tilemap in the world, taken from the map entity and its layer every frame.
+#[derive(Default)]
+pub struct MapGeometry {
+    /// World position of the bottom left corner of the first tile.
+    pub origin: Vec2,
+    pub tile_size: Vec2,
+    /// Size of the map in tiles.
+    pub size: UVec2,
+}
+
+impl MapGeometry {
+    /// Tile containing the world position, `None` if it is outside the map.
+    pub fn tile_at(&self, world_pos: Vec2) -> Option<TilePos> {
+        if self.tile_size.x <= 0.0 || self.tile_size.y <= 0.0 {
+            return None;
+        }
+
+        let tile = ((world_pos - self.origin) / self.tile_size).floor();
+
+        if tile.x < 0.0 || tile.y < 0.0 || tile.x >= self.size.x as f32 || tile.y >= self.size.y as f32 {
+            return None;
+        }
+
+        Some(TilePos(tile.x as u32, tile.y as u32))
+    }
+
+    pub fn tile_center(&self, tile_pos: TilePos) -> Vec2 {
+        self.origin + (vec2(tile_pos.0 as f32, tile_pos.1 as f32) + 0.5) * self.tile_size
+    }
+
+    /// Bottom left and top right corners of the map in world coordinates.
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        (self.origin, self.origin + self.size.as_vec2() * self.tile_size)
+    }
+}
+
+/// Where the player is pointing this frame, with either the mouse or the gamepad cursor.
+#[derive(Default)]
+pub struct Pointer {
+    /// Window position, from the bottom left corner.
+    pub screen: Option<Vec2>,
+    pub world: Option<Vec2>,
+    /// Tile under the pointer, `None` if it is outside the map.
+    pub tile: Option<TilePos>,
+    /// Whether the pointer is over a UI element rather than the map.
+    pub over_ui: bool,
+}
+
+pub fn update_pointer(mut pointer: ResMut<Pointer>,
+                      mut geometry: ResMut<MapGeometry>,
+                      camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+                      map: Query<&Transform, With<Map>>,
+                      map_query: MapQuery,
+                      interactions: Query<&Interaction>,
+                      gamepad_cursor: Res<GamepadCursor>,
+                      windows: Res<Windows>)
+{
+    if let (Ok(map_transform), Some((_, layer))) = (map.get_single(), map_query.get_layer(0u16, 0u16)) {
+        let settings = &layer.settings;
+
+        geometry.origin = map_transform.translation.truncate();
+        geometry.tile_size = vec2(settings.tile_size.0, settings.tile_size.1);
+        geometry.size = UVec2::new(
+            settings.map_size.0 * settings.chunk_size.0,
+            settings.map_size.1 * settings.chunk_size.1,
+        );
+    }
+
+    *pointer = Pointer::default();
+
+    let (window, (camera_transform, projection)) = match (windows.get_primary(), camera.get_single()) {
+        (Some(window), Ok(camera)) => (window, camera),
+        _ => return,
+    };
+    let window_size = vec2(window.width(), window.height());
+
+    // The camera components are used directly instead of `Camera` and `GlobalTransform`,
+    // which are only updated at the end of the frame and would lag behind panning and zooming
+    let world_to_ndc = projection.get_projection_matrix() * camera_transform.compute_matrix().inverse();
+
+    if gamepad_cursor.active {
+        let ndc = world_to_ndc.project_point3(gamepad_cursor.position.extend(0.0)).truncate();
+
+        pointer.screen = Some((ndc + Vec2::ONE) / 2.0 * window_size);
+        pointer.world = Some(gamepad_cursor.position);
+    } else if let Some(screen_pos) = window.cursor_position() {
+        let ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
+
+        pointer.screen = Some(screen_pos);
+        pointer.world = Some(world_to_ndc.inverse().project_point3(ndc.extend(-1.0)).truncate());
+        pointer.over_ui = interactions.iter().any(|interaction| *interaction != Interaction::None);
+    }
+
+    pointer.tile = pointer.world.and_then(|world_pos| geometry.tile_at(world_pos));
+}
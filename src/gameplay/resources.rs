@@ -1,33 +1,137 @@
+use std::collections::VecDeque;
+use std::f32::consts::PI;
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
 use crate::gameplay::components::*;
+use rand::random;
+use crate::gameplay::balance::Balance;
+use crate::gameplay::placement::BuildAction;
 
+/// Currency balances, indexed by [`Species`].
 #[derive(Inspectable, Default)]
 pub struct Wallet {
-    pub red_squares: u32,
-    pub green_triangles: u32,
-    pub blue_circles: u32,
+    pub balances: Vec<u32>,
 }
 
 impl Wallet {
+    pub fn new(species_count: usize, amount: u32) -> Wallet {
+        Wallet {
+            balances: vec![amount; species_count],
+        }
+    }
+
+    pub fn get(&self, species: Species) -> u32 {
+        self.balances.get(species.0).copied().unwrap_or(0)
+    }
+
+    pub fn add(&mut self, money: Money) {
+        if self.balances.len() <= money.species.0 {
+            self.balances.resize(money.species.0 + 1, 0);
+        }
+
+        self.balances[money.species.0] += money.amount;
+    }
+
     pub fn try_spend(&mut self, money: Money) -> bool {
-        match money.species {
-            Species::Red => if self.red_squares >= money.amount {
-                self.red_squares -= money.amount;
-                return true;
-            },
-            Species::Green => if self.green_triangles >= money.amount {
-                self.green_triangles -= money.amount;
-                return true;
-            },
-            Species::Blue => if self.blue_circles >= money.amount {
-                self.blue_circles -= money.amount;
-                return true;
+        match self.balances.get_mut(money.species.0) {
+            Some(balance) if *balance >= money.amount => {
+                *balance -= money.amount;
+                true
             },
+            _ => false,
+        }
+    }
+}
+
+/// A monster which is going to spawn.
+#[derive(Copy, Clone)]
+pub struct PlannedSpawn {
+    pub species: Species,
+    pub health: i32,
+    /// Direction from the core the monster comes from.
+    pub angle: f32,
+    /// Time between the previous spawn and this one.
+    pub delay: f32,
+}
+
+/// Number of monsters planned ahead of time.
+pub const PLANNED_SPAWNS: usize = 5;
+
+/// Upcoming monster spawns, decided ahead of time so they can be shown to the player.
+pub struct SpawnSchedule {
+    /// Time until the first upcoming monster spawns.
+    pub cooldown: f32,
+    pub upcoming: VecDeque<PlannedSpawn>,
+    /// Health of the monster planned after the upcoming ones.
+    next_health: i32,
+}
+
+impl SpawnSchedule {
+    pub fn new(balance: &Balance, species_count: usize) -> SpawnSchedule {
+        let mut schedule = SpawnSchedule {
+            cooldown: balance.first_spawn_delay,
+            upcoming: VecDeque::new(),
+            next_health: balance.monster_health,
+        };
+
+        schedule.plan(balance, species_count);
+        schedule.upcoming[0].delay = balance.first_spawn_delay;
+
+        schedule
+    }
+
+    /// Tops up the upcoming spawns to [`PLANNED_SPAWNS`].
+    pub fn plan(&mut self, balance: &Balance, species_count: usize) {
+        while self.upcoming.len() < PLANNED_SPAWNS {
+            let (min_interval, max_interval) = balance.spawn_interval;
+
+            self.upcoming.push_back(PlannedSpawn {
+                species: Species(random::<usize>() % species_count),
+                health: self.next_health,
+                angle: random::<f32>() * PI * 2.0,
+                delay: min_interval + random::<f32>() * (max_interval - min_interval),
+            });
+
+            self.next_health += balance.monster_health_growth;
         }
+    }
 
-        return false;
+    /// Takes the first upcoming monster and starts counting down to the next one.
+    pub fn pop(&mut self, balance: &Balance, species_count: usize) -> PlannedSpawn {
+        let spawn = self.upcoming.pop_front().unwrap();
+
+        self.plan(balance, species_count);
+        self.cooldown += self.upcoming[0].delay;
+
+        spawn
     }
 }
 
-pub struct MonsterSpawnCooldown(pub f32, pub i32);
+/// Recent building actions which can still be undone or redone.
+#[derive(Default)]
+pub struct BuildHistory {
+    pub done: Vec<BuildAction>,
+    pub undone: Vec<BuildAction>,
+}
+
+impl BuildHistory {
+    pub fn record(&mut self, action: BuildAction) {
+        self.done.push(action);
+        self.undone.clear();
+    }
+}
+
+/// Tile-snapped cursor moved with a gamepad. Replaces the mouse cursor from the moment the stick is touched
+/// until the mouse is moved again.
+#[derive(Default)]
+pub struct GamepadCursor {
+    pub active: bool,
+    /// World position of the center of the tile under the cursor.
+    pub position: Vec2,
+    /// Time until the held stick moves the cursor by another tile, `None` if the stick is released.
+    pub repeat_cooldown: Option<f32>,
+}
+
+/// Seconds left before the game is lost, counting down once a monster reached the core.
+#[derive(Default)]
+pub struct DefeatCountdown(pub Option<f32>);
\ No newline at end of file
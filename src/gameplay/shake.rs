@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use crate::gameplay::balance::Balance;
+use crate::gameplay::components::MainCamera;
+use crate::gameplay::events::{CoreDestroyed, MonsterKilled};
+use crate::settings::Settings;
+
+/// Trauma lost every second. Shaking stops when it reaches zero.
+const TRAUMA_DECAY: f32 = 1.5;
+/// Camera offset at full trauma, in screen pixels.
+const MAX_SHAKE_OFFSET: f32 = 12.0;
+/// A monster reaching the core loses the game, so it shakes at full trauma.
+const CORE_DESTROYED_TRAUMA: f32 = 1.0;
+const BIG_KILL_TRAUMA: f32 = 0.3;
+/// Monsters with this many times the starting monster health count as big.
+const BIG_MONSTER_FACTOR: f32 = 1.5;
+
+/// Seconds the action freezes for.
+const CORE_DESTROYED_HIT_STOP: f32 = 0.12;
+const BIG_KILL_HIT_STOP: f32 = 0.06;
+
+/// Shake of the main camera, driven by trauma between 0 and 1.
+#[derive(Default)]
+pub struct CameraShake {
+    trauma: f32,
+    /// Offset added to the camera this frame, taken off again before the camera moves.
+    offset: Vec2,
+}
+
+impl CameraShake {
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+}
+
+/// Short freeze of the monsters, bullets and cannons, to make heavy hits land.
+#[derive(Default)]
+pub struct HitStop {
+    remaining: f32,
+}
+
+impl HitStop {
+    pub fn active(&self) -> bool {
+        self.remaining > 0.0
+    }
+
+    fn start(&mut self, duration: f32) {
+        self.remaining = self.remaining.max(duration);
+    }
+}
+
+pub fn shake_on_impact(mut core_destroyed: EventReader<CoreDestroyed>,
+                       mut kills: EventReader<MonsterKilled>,
+                       mut shake: ResMut<CameraShake>,
+                       mut hit_stop: ResMut<HitStop>,
+                       settings: Res<Settings>,
+                       balance: Res<Balance>,
+                       time: Res<Time>)
+{
+    hit_stop.remaining -= time.delta_seconds();
+
+    let mut impacts = Vec::new();
+
+    for _ in core_destroyed.iter() {
+        impacts.push((CORE_DESTROYED_TRAUMA, CORE_DESTROYED_HIT_STOP));
+    }
+
+    let big_health = balance.monster_health as f32 * BIG_MONSTER_FACTOR;
+    for kill in kills.iter() {
+        if kill.max_health as f32 >= big_health {
+            impacts.push((BIG_KILL_TRAUMA, BIG_KILL_HIT_STOP));
+        }
+    }
+
+    if settings.reduced_motion {
+        return;
+    }
+
+    for (trauma, hit_stop_duration) in impacts {
+        shake.add_trauma(trauma);
+
+        if settings.hit_stop {
+            hit_stop.start(hit_stop_duration);
+        }
+    }
+}
+
+/// Takes last frame's shake off the camera, so panning and zooming work from its actual position.
+pub fn reset_camera_shake(mut camera: Query<&mut Transform, With<MainCamera>>,
+                          mut shake: ResMut<CameraShake>)
+{
+    let mut transform = camera.single_mut();
+
+    transform.translation -= shake.offset.extend(0.0);
+    shake.offset = Vec2::ZERO;
+}
+
+pub fn apply_camera_shake(mut camera: Query<(&mut Transform, &OrthographicProjection), With<MainCamera>>,
+                          mut shake: ResMut<CameraShake>,
+                          settings: Res<Settings>,
+                          time: Res<Time>)
+{
+    shake.trauma = (shake.trauma - TRAUMA_DECAY * time.delta_seconds()).max(0.0);
+
+    if settings.reduced_motion || shake.trauma == 0.0 {
+        return;
+    }
+
+    let (mut transform, projection) = camera.single_mut();
+
+    // Squared trauma makes small shakes subtle and big ones violent
+    let amount = shake.trauma * shake.trauma * MAX_SHAKE_OFFSET;
+    let t = time.seconds_since_startup() as f32;
+    let noise = Vec2::new(
+        (t * 41.0).sin() + (t * 67.0).sin() * 0.5,
+        (t * 53.0).cos() + (t * 79.0).cos() * 0.5,
+    ) / 1.5;
+
+    // Whole screen pixels, like the rest of the camera movement
+    let offset = (noise * amount).round() * projection.scale;
+
+    transform.translation += offset.extend(0.0);
+    shake.offset = offset;
+}
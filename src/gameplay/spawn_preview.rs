@@ -0,0 +1,112 @@
+use bevy::math::vec3;
+use bevy::prelude::*;
+use crate::{GameState, PreloadedAssets};
+use crate::cleanup::StateScoped;
+use crate::localization::Localization;
+use crate::theme::{spawn_panel, Skin, Theme};
+use crate::gameplay::resources::{SpawnSchedule, PLANNED_SPAWNS};
+use crate::gameplay::species::SpeciesTable;
+
+#[derive(Component)]
+pub struct SpawnCountdown;
+
+/// Image of the n-th upcoming monster.
+#[derive(Component)]
+pub struct SpawnPreviewIcon(pub usize);
+
+/// Health of the n-th upcoming monster.
+#[derive(Component)]
+pub struct SpawnPreviewHealth(pub usize);
+
+pub fn spawn_preview_panel(commands: &mut Commands, pre_assets: &PreloadedAssets, theme: &Theme) {
+    let text_style = TextStyle {
+        font: pre_assets.font.clone(),
+        font_size: 56.0,
+        color: theme.text,
+    };
+
+    let content = commands.spawn_bundle(NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::FlexStart,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }).with_children(|panel| {
+        panel.spawn_bundle(TextBundle {
+            text: Text::with_section("", text_style.clone(), default()),
+            transform: Transform {
+                scale: vec3(0.25, 0.25, 1.0) * 2.0,
+                ..default()
+            },
+            ..default()
+        }).insert(SpawnCountdown);
+
+        for i in 0..PLANNED_SPAWNS {
+            panel.spawn_bundle(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                color: Color::NONE.into(),
+                ..default()
+            }).with_children(|row| {
+                row.spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(24.0 * 2.0), Val::Px(24.0 * 2.0)),
+                        ..default()
+                    },
+                    ..default()
+                }).insert(SpawnPreviewIcon(i));
+
+                row.spawn_bundle(TextBundle {
+                    text: Text::with_section("", text_style.clone(), default()),
+                    transform: Transform {
+                        scale: vec3(0.25, 0.25, 1.0) * 2.0,
+                        ..default()
+                    },
+                    ..default()
+                }).insert(SpawnPreviewHealth(i));
+            });
+        }
+    }).id();
+
+    let panel = spawn_panel(commands, theme, Skin::Panel, Style {
+        position_type: PositionType::Absolute,
+        position: Rect {
+            left: Val::Px(12.0),
+            top: Val::Px(12.0),
+            ..default()
+        },
+        ..default()
+    }, content);
+
+    commands.entity(panel).insert(StateScoped(GameState::Gameplay));
+}
+
+pub fn update_spawn_preview(schedule: Res<SpawnSchedule>,
+                            species_table: Res<SpeciesTable>,
+                            localization: Res<Localization>,
+                            mut countdown: Query<&mut Text, With<SpawnCountdown>>,
+                            mut icons: Query<(&mut UiImage, &SpawnPreviewIcon)>,
+                            mut healths: Query<(&mut Text, &SpawnPreviewHealth), Without<SpawnCountdown>>)
+{
+    for mut text in countdown.iter_mut() {
+        let seconds = format!("{:.1}", schedule.cooldown.max(0.0));
+        text.sections[0].value = localization.format("spawn.countdown", &[("seconds", &seconds)]);
+    }
+
+    for (mut image, icon) in icons.iter_mut() {
+        if let Some(spawn) = schedule.upcoming.get(icon.0) {
+            image.0 = species_table.get(spawn.species).monster.clone();
+        }
+    }
+
+    for (mut text, health) in healths.iter_mut() {
+        if let Some(spawn) = schedule.upcoming.get(health.0) {
+            text.sections[0].value = localization.format("spawn.health", &[("health", &spawn.health)]);
+            text.sections[0].style.color = species_table.get(spawn.species).color;
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use anyhow::anyhow;
+use bevy::asset::{Asset, AssetLoader, AssetPath, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::math::vec2;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy_kira_audio::AudioSource;
+use serde::Deserialize;
+use crate::gameplay::components::Species;
+
+/// All monster species known to the game, in the order they appear in the UI.
+///
+/// Loaded from a `.species.ron` file so that new species can be added without touching the code.
+#[derive(TypeUuid, Clone)]
+#[uuid = "6b1f2a8e-2f4b-4c55-9a0e-4d6f3e1c7a21"]
+pub struct SpeciesTable {
+    pub species: Vec<SpeciesInfo>,
+}
+
+#[derive(Clone)]
+pub struct SpeciesInfo {
+    pub name: String,
+    pub color: Color,
+    pub currency_icon: Handle<Image>,
+    pub currency_icon_small: Handle<Image>,
+    pub currency_icon_small_size: Vec2,
+    pub monster: Handle<Image>,
+    pub cannon: Handle<Image>,
+    pub bullet: Handle<Image>,
+    pub fire_sound: Handle<AudioSource>,
+    /// Sprites of the converters from this species, by the species they convert to.
+    pub converters: HashMap<Species, Handle<Image>>,
+}
+
+impl SpeciesTable {
+    pub fn len(&self) -> usize {
+        self.species.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Species> {
+        (0..self.species.len()).map(Species)
+    }
+
+    pub fn get(&self, species: Species) -> &SpeciesInfo {
+        &self.species[species.0]
+    }
+
+    /// Handles of every asset the table refers to.
+    pub fn dependencies(&self) -> Vec<HandleUntyped> {
+        let mut handles = Vec::new();
+
+        for info in &self.species {
+            handles.extend([
+                info.currency_icon.clone_untyped(),
+                info.currency_icon_small.clone_untyped(),
+                info.monster.clone_untyped(),
+                info.cannon.clone_untyped(),
+                info.bullet.clone_untyped(),
+                info.fire_sound.clone_untyped(),
+            ]);
+            handles.extend(info.converters.values().map(|converter| converter.clone_untyped()));
+        }
+
+        handles
+    }
+
+    pub fn find(&self, name: &str) -> Option<Species> {
+        self.species.iter().position(|info| info.name == name).map(Species)
+    }
+}
+
+#[derive(Deserialize)]
+struct SpeciesTableFile {
+    species: Vec<SpeciesFile>,
+}
+
+#[derive(Deserialize)]
+struct SpeciesFile {
+    name: String,
+    /// Hex colour, e.g. `"ff1a00"`.
+    color: String,
+    currency_icon: String,
+    currency_icon_small: String,
+    currency_icon_small_size: (f32, f32),
+    monster: String,
+    cannon: String,
+    bullet: String,
+    fire_sound: String,
+    /// Converter sprites, by the name of the species they convert to.
+    #[serde(default)]
+    converters: HashMap<String, String>,
+}
+
+/// Gets a handle to an asset the species table refers to, making sure it gets loaded with the table.
+fn dependency<T: Asset>(load_context: &LoadContext, dependencies: &mut Vec<AssetPath<'static>>, path: &str) -> Handle<T> {
+    dependencies.push(AssetPath::from(path).to_owned());
+    load_context.get_handle(path)
+}
+
+#[derive(Default)]
+pub struct SpeciesTableLoader;
+
+impl AssetLoader for SpeciesTableLoader {
+    fn load<'a>(&'a self, bytes: &'a [u8], load_context: &'a mut LoadContext) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let file: SpeciesTableFile = ron::de::from_bytes(bytes)?;
+
+            let mut dependencies = Vec::new();
+
+            let names: Vec<String> = file.species.iter().map(|entry| entry.name.clone()).collect();
+
+            let mut species = Vec::new();
+            for entry in file.species {
+                let mut converters = HashMap::new();
+                for (target, path) in &entry.converters {
+                    let index = names.iter().position(|name| name == target)
+                        .ok_or_else(|| anyhow!("species {} has a converter to unknown species {}", entry.name, target))?;
+
+                    converters.insert(Species(index), dependency(load_context, &mut dependencies, path));
+                }
+
+                let color = Color::hex(&entry.color)
+                    .map_err(|err| anyhow!("invalid colour {:?} for species {}: {:?}", entry.color, entry.name, err))?;
+
+                species.push(SpeciesInfo {
+                    name: entry.name,
+                    color,
+                    currency_icon: dependency(load_context, &mut dependencies, &entry.currency_icon),
+                    currency_icon_small: dependency(load_context, &mut dependencies, &entry.currency_icon_small),
+                    currency_icon_small_size: vec2(entry.currency_icon_small_size.0, entry.currency_icon_small_size.1),
+                    monster: dependency(load_context, &mut dependencies, &entry.monster),
+                    cannon: dependency(load_context, &mut dependencies, &entry.cannon),
+                    bullet: dependency(load_context, &mut dependencies, &entry.bullet),
+                    fire_sound: dependency(load_context, &mut dependencies, &entry.fire_sound),
+                    converters,
+                });
+            }
+
+            if species.is_empty() {
+                return Err(anyhow!("species table is empty"));
+            }
+
+            load_context.set_default_asset(LoadedAsset::new(SpeciesTable { species })
+                .with_dependencies(dependencies));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["species.ron"]
+    }
+}
@@ -1,15 +1,22 @@
-use std::f32::consts::PI;
-use bevy::math::{vec2, vec3};
+use bevy::math::{const_vec2, vec2, vec3};
 use bevy::prelude::*;
-use bevy::render::camera::RenderTarget;
-use bevy_ecs_tilemap::{Map, MapQuery, MapTileError, Tile, TilePos};
-use rand::random;
-use gameplay::{TILE_CORE, TILE_CONNECTOR};
+use bevy::sprite::Anchor;
+use bevy_ecs_tilemap::{MapQuery, Tile};
 use crate::assets::GameplayAssets;
-use crate::{gameplay, GameState};
+use crate::{palette, GameState};
+use crate::cleanup::StateScoped;
 use crate::gameplay::components::*;
-use crate::gameplay::resources::{MonsterSpawnCooldown, Wallet};
-use crate::gameplay::{TILE_CANNON, TILE_NONE};
+use crate::gameplay::resources::{BuildHistory, DefeatCountdown, GamepadCursor, SpawnSchedule, Wallet};
+use crate::gameplay::shake::HitStop;
+use crate::gameplay::item_image;
+use crate::gameplay::placement::{build, check_placement, demolish, BuildAction, PlacementError};
+use crate::gameplay::pointer::{MapGeometry, Pointer};
+use crate::gameplay::species::SpeciesTable;
+use crate::gameplay::balance::Balance;
+use crate::gameplay::effects::{die, recoil, spawn_placement_flash};
+use crate::gameplay::events::{CannonFired, CoreDestroyed, ItemPlaced, MonsterHit, MonsterKilled, PlacementDenied};
+use crate::input::{Action, ActionState, Bindings, ITEM_SLOTS};
+use crate::localization::Localization;
 
 pub fn core_spinner(mut query: Query<&mut Transform, With<CoreSpinner>>, time: Res<Time>) {
     for mut transform in query.iter_mut() {
@@ -19,243 +26,381 @@ pub fn core_spinner(mut query: Query<&mut Transform, With<CoreSpinner>>, time: R
 
 pub fn wallet_display(mut query: Query<(&mut Text, &WalletDisplay)>, wallet: Res<Wallet>) {
     for (mut text, wallet_display) in query.iter_mut() {
-        use Species::*;
-
-        let value = match wallet_display.0 {
-            Red => wallet.red_squares,
-            Green => wallet.green_triangles,
-            Blue => wallet.blue_circles,
-        };
-
-        text.sections[0].value = value.to_string();
+        text.sections[0].value = wallet.get(wallet_display.0).to_string();
     }
 }
 
 pub fn buy_item(mut commands: Commands,
-                query: Query<(&Interaction, &BuyItemButton), Changed<Interaction>>,
+                query: Query<(&Interaction, &BuyItemButton, &ItemSlot), Changed<Interaction>>,
                 ghosts: Query<(), With<ItemPlacementGhost>>,
-                game_assets: Res<GameplayAssets>)
+                game_assets: Res<GameplayAssets>,
+                species_table: Res<SpeciesTable>)
 {
-    for (interaction, buy_item_btn) in query.iter() {
+    for (interaction, buy_item_btn, slot) in query.iter() {
         match interaction {
             Interaction::Clicked => if ghosts.get_single().is_err() {
-                let item_image = match buy_item_btn.item {
-                    Item::Connector => game_assets.connector.clone(),
-                    Item::RedCannon => game_assets.cannon_red.clone(),
-                    Item::GreenCannon => game_assets.cannon_green.clone(),
-                    Item::BlueCannon => game_assets.cannon_blue.clone(),
-                    Item::ConverterRedGreen => game_assets.converter_red_green.clone(),
-                    Item::ConverterRedBlue => game_assets.converter_red_blue.clone(),
-                    Item::ConverterGreenRed => game_assets.converter_green_red.clone(),
-                    Item::ConverterGreenBlue => game_assets.converter_green_blue.clone(),
-                    Item::ConverterBlueRed => game_assets.converter_blue_red.clone(),
-                    Item::ConverterBlueGreen => game_assets.converter_blue_green.clone(),
-                };
-
-                commands.spawn_bundle(SpriteBundle {
-                    texture: item_image,
-                    transform: Transform::from_xyz(0.0, 0.0, 100.0),
-                    visibility: Visibility { is_visible: false },
-                    ..default()
-                }).insert(ItemPlacementGhost)
-                    .insert(buy_item_btn.cost)
-                    .insert(buy_item_btn.item);
+                spawn_ghost(&mut commands, buy_item_btn, *slot, &game_assets, &species_table);
             },
             _ => ()
         }
     }
 }
 
-pub fn drag_ghost(windows: Res<Windows>,
-                  camera: Query<(&Camera, &GlobalTransform), (With<MainCamera>, Without<Map>)>,
-                  mut ghost: Query<(Entity, &mut Transform, &mut Sprite, &mut Visibility, &Item, &Money), With<ItemPlacementGhost>>,
-                  mut map_query: MapQuery,
-                  tile_query: Query<&Tile>,
-                  mouse_buttons: Res<Input<MouseButton>>,
-                  mut commands: Commands,
-                  mut wallet: ResMut<Wallet>,
-                  game_assets: Res<GameplayAssets>)
+/// Label of the first button selecting the item in the slot, if there is one.
+pub fn slot_hotkey_label(slot: ItemSlot, bindings: &Bindings, localization: &Localization) -> Option<String> {
+    bindings.of(Action::SelectItem(slot.0)).next().map(|binding| binding.name(localization))
+}
+
+pub fn hotkey_labels(mut labels: Query<(&mut Text, &HotkeyLabel)>,
+                     added: Query<(), Added<HotkeyLabel>>,
+                     bindings: Res<Bindings>,
+                     localization: Res<Localization>)
 {
-    let (ghost_entity, mut ghost_transform, mut ghost_sprite, mut ghost_visibility, item, cost) = match ghost.get_single_mut() {
-        Ok(t) => t,
-        Err(_) => return,
-    };
+    if added.is_empty() && !bindings.is_changed() && !localization.is_changed() {
+        return;
+    }
 
-    ghost_visibility.is_visible = true;
-    ghost_sprite.color = Color::rgba(1.0, 1.0, 1.0, 0.5);
+    for (mut text, label) in labels.iter_mut() {
+        text.sections[0].value = slot_hotkey_label(label.0, &bindings, &localization).unwrap_or_default();
+    }
+}
 
-    let (camera, camera_transform) = camera.single();
+/// Keeps the prices on the buy panel in line with the buttons, which change when the balance is reloaded.
+pub fn cost_labels(buttons: Query<(&BuyItemButton, &ItemSlot), Changed<BuyItemButton>>,
+                   mut labels: Query<(&mut Text, &CostLabel)>,
+                   mut icons: Query<(&mut UiImage, &mut Style, &CostIcon)>,
+                   species_table: Res<SpeciesTable>)
+{
+    for (button, slot) in buttons.iter() {
+        let info = species_table.get(button.cost.species);
 
-    let window = if let RenderTarget::Window(id) = camera.target {
-        windows.get(id).unwrap()
-    } else {
-        windows.get_primary().unwrap()
-    };
+        for (mut text, _) in labels.iter_mut().filter(|(_, label)| label.0 == *slot) {
+            text.sections[0].value = button.cost.amount.to_string();
+            text.sections[0].style.color = info.color;
+        }
 
-    if let Some(screen_pos) = window.cursor_position() {
-        let window_size = Vec2::new(window.width() as f32, window.height() as f32);
+        for (mut image, mut style, _) in icons.iter_mut().filter(|(_, _, icon)| icon.0 == *slot) {
+            let size = info.currency_icon_small_size;
 
-        let ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
+            image.0 = info.currency_icon_small.clone();
+            style.size = Size::new(Val::Px(size.x * 3.0), Val::Px(size.y * 3.0));
+        }
+    }
+}
 
-        let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix.inverse();
+pub fn item_hotkeys(mut commands: Commands,
+                    actions: Res<ActionState>,
+                    buttons: Query<(&BuyItemButton, &ItemSlot)>,
+                    ghosts: Query<(Entity, &ItemSlot), With<ItemPlacementGhost>>,
+                    game_assets: Res<GameplayAssets>,
+                    species_table: Res<SpeciesTable>)
+{
+    let slot_count = buttons.iter().count();
+    if slot_count == 0 {
+        return;
+    }
 
-        let world_pos = ndc_to_world.project_point3(ndc.extend(-1.0));
+    let current = ghosts.get_single().ok();
+    let current_slot = current.map(|(_, slot)| slot.0);
 
-        ghost_transform.translation.x = world_pos.x;
-        ghost_transform.translation.y = world_pos.y;
+    let mut selected = (0..ITEM_SLOTS).find(|slot| actions.just_pressed(Action::SelectItem(*slot)));
 
-        let map_offset = -24.0 * 8.0 * 2.0 - 12.0;
-        let tile_pos = TilePos(((world_pos.x - map_offset) / 24.0) as u32, ((world_pos.y - map_offset) / 24.0) as u32);
+    if actions.just_pressed(Action::NextItem) {
+        selected = Some(current_slot.map_or(0, |slot| (slot + 1) % slot_count));
+    }
+    if actions.just_pressed(Action::PreviousItem) {
+        selected = Some(current_slot.map_or(slot_count - 1, |slot| (slot + slot_count - 1) % slot_count));
+    }
 
-        match map_query.get_tile_entity(tile_pos, 0, 0) {
-            Ok(tile_entity) => {
-                let tile = tile_query.get(tile_entity).unwrap();
+    let selected = match selected {
+        Some(selected) if Some(selected) != current_slot => selected,
+        _ => return,
+    };
 
-                if tile.texture_index != TILE_NONE {
-                    return;
-                }
-            },
-            Err(err) => match err {
-                MapTileError::OutOfBounds(_) => return,
-                _ => (),
-            }
+    if let Some((button, slot)) = buttons.iter().find(|(_, slot)| slot.0 == selected) {
+        if let Some((ghost_entity, _)) = current {
+            commands.entity(ghost_entity).despawn();
         }
 
-        let neighbors = map_query.get_tile_neighbors(tile_pos, 0, 0);
+        spawn_ghost(&mut commands, button, *slot, &game_assets, &species_table);
+    }
+}
 
-        let mut neighbor_dir = 10000;
-        for i in 0..4 {
-            let neighbor = neighbors[i];
+fn spawn_ghost(commands: &mut Commands,
+               button: &BuyItemButton,
+               slot: ItemSlot,
+               game_assets: &GameplayAssets,
+               species_table: &SpeciesTable) {
+    commands.spawn_bundle(SpriteBundle {
+        texture: item_image(button.item, game_assets, species_table),
+        transform: Transform::from_xyz(0.0, 0.0, 100.0),
+        visibility: Visibility { is_visible: false },
+        ..default()
+    }).insert(ItemPlacementGhost)
+        .insert(GhostOrientation(Side::North))
+        .insert(PlacementFeedback(None))
+        .insert(slot)
+        .insert(button.cost)
+        .insert(button.item)
+        .insert(StateScoped(GameState::Gameplay));
+}
 
-            if let Ok(tile_entity) = neighbor {
-                let tile = tile_query.get(tile_entity).unwrap();
+const STICK_DEADZONE: f32 = 0.5;
+/// Delay before a held stick starts repeating the cursor movement.
+const CURSOR_REPEAT_DELAY: f32 = 0.3;
+const CURSOR_REPEAT_INTERVAL: f32 = 0.08;
+
+pub fn move_gamepad_cursor(mut cursor: ResMut<GamepadCursor>,
+                           mut highlight: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<GamepadCursorHighlight>>,
+                           geometry: Res<MapGeometry>,
+                           gamepads: Res<Gamepads>,
+                           axes: Res<Axis<GamepadAxis>>,
+                           buttons: Res<Input<GamepadButton>>,
+                           mut cursor_moved: EventReader<CursorMoved>,
+                           time: Res<Time>)
+{
+    if cursor_moved.iter().count() > 0 {
+        cursor.active = false;
+    }
 
-                match tile.texture_index {
-                    TILE_CORE | TILE_CONNECTOR => {
-                        ghost_sprite.color = Color::rgba(1.0, 1.0, 1.0, 1.0);
-                        ghost_transform.translation.x = (ghost_transform.translation.x / 24.0 + 0.5).floor() * 24.0;
-                        ghost_transform.translation.y = (ghost_transform.translation.y / 24.0 + 0.5).floor() * 24.0;
-                        neighbor_dir = i;
-                        break;
-                    },
-                    _ => continue,
-                }
+    let mut direction = Vec2::ZERO;
+    for gamepad in gamepads.iter() {
+        let stick_x = axes.get(GamepadAxis(*gamepad, GamepadAxisType::LeftStickX)).unwrap_or(0.0);
+        let stick_y = axes.get(GamepadAxis(*gamepad, GamepadAxisType::LeftStickY)).unwrap_or(0.0);
+        direction += vec2(stick_x, stick_y);
+
+        let dpad = [
+            (GamepadButtonType::DPadUp, Vec2::Y),
+            (GamepadButtonType::DPadDown, -Vec2::Y),
+            (GamepadButtonType::DPadLeft, -Vec2::X),
+            (GamepadButtonType::DPadRight, Vec2::X),
+        ];
+        for (button, offset) in dpad {
+            if buttons.pressed(GamepadButton(*gamepad, button)) {
+                direction += offset;
             }
         }
+    }
 
-        if mouse_buttons.just_released(MouseButton::Right) {
-            commands.entity(ghost_entity).despawn();
-        }
+    let step = vec2(
+        if direction.x.abs() > STICK_DEADZONE { direction.x.signum() } else { 0.0 },
+        if direction.y.abs() > STICK_DEADZONE { direction.y.signum() } else { 0.0 },
+    );
 
-        if mouse_buttons.just_released(MouseButton::Left) && neighbor_dir < 10 && wallet.try_spend(*cost) {
-            let (flip_x, flip_y, flip_d) = match neighbor_dir {
-                0 => (false, false, true), // N
-                1 => (false, true, true), // S
-                2 => (false, false, false), // W
-                3 => (true, false, false), // E
-                _ => unreachable!(),
-            };
+    if step == Vec2::ZERO {
+        cursor.repeat_cooldown = None;
+    } else {
+        let cooldown = cursor.repeat_cooldown.map(|cooldown| cooldown - time.delta_seconds());
+
+        match cooldown {
+            Some(cooldown) if cooldown > 0.0 => cursor.repeat_cooldown = Some(cooldown),
+            _ => {
+                // The first touch only brings the cursor back without moving it
+                let target = if cursor.active {
+                    cursor.position + step * geometry.tile_size
+                } else {
+                    cursor.position
+                };
 
-            match item {
-                Item::Connector => {
-                    let tile = Tile {
-                        texture_index: TILE_CONNECTOR,
-                        ..default()
-                    };
-                    map_query.set_tile(&mut commands, tile_pos, tile, 0, 0).unwrap();
+                // Stays on the last tile when pushed against the map edge
+                if let Some(tile_pos) = geometry.tile_at(target) {
+                    cursor.position = geometry.tile_center(tile_pos);
                 }
 
-                Item::RedCannon => {
-                    let tile = Tile {
-                        texture_index: TILE_CANNON,
-                        flip_x,
-                        flip_y,
-                        flip_d,
-                        ..default()
-                    };
-                    let cannon_entity = map_query.set_tile(&mut commands, tile_pos, tile, 0, 0).unwrap();
+                cursor.active = true;
+                cursor.repeat_cooldown = Some(if cooldown.is_some() { CURSOR_REPEAT_INTERVAL } else { CURSOR_REPEAT_DELAY });
+            },
+        }
+    }
 
-                    let cannon_head = commands.spawn_bundle(SpriteBundle {
-                        texture: game_assets.cannon_red.clone(),
-                        transform: Transform {
-                            translation: vec3(ghost_transform.translation.x, ghost_transform.translation.y, 0.3),
-                            ..default()
-                        },
-                        ..default()
-                    }).insert(Cannon {
-                        species: Species::Red,
-                        cooldown: 0.0,
-                    }).id();
+    for (mut transform, mut sprite, mut visibility) in highlight.iter_mut() {
+        visibility.is_visible = cursor.active;
+        sprite.custom_size = Some(geometry.tile_size);
+        transform.translation.x = cursor.position.x;
+        transform.translation.y = cursor.position.y;
+    }
+}
 
-                    commands.entity(cannon_entity).insert(CannonBase(cannon_head));
-                }
+pub fn drag_ghost(mut ghost: Query<(Entity, &mut Transform, &mut Sprite, &mut Visibility, &mut GhostOrientation, &mut PlacementFeedback, &Item, &Money), With<ItemPlacementGhost>>,
+                  mut map_query: MapQuery,
+                  tile_query: Query<&Tile>,
+                  actions: Res<ActionState>,
+                  pointer: Res<Pointer>,
+                  geometry: Res<MapGeometry>,
+                  mut commands: Commands,
+                  mut wallet: ResMut<Wallet>,
+                  mut history: ResMut<BuildHistory>,
+                  mut placed: EventWriter<ItemPlaced>,
+                  mut denied: EventWriter<PlacementDenied>,
+                  species_table: Res<SpeciesTable>,
+                  time: Res<Time>)
+{
+    let mut rotation_steps = 0;
+    if actions.just_pressed(Action::RotateClockwise) {
+        rotation_steps += 1;
+    }
+    if actions.just_pressed(Action::RotateCounterClockwise) {
+        rotation_steps -= 1;
+    }
 
-                Item::GreenCannon => {
-                    let tile = Tile {
-                        texture_index: TILE_CANNON,
-                        flip_x,
-                        flip_y,
-                        flip_d,
-                        ..default()
-                    };
-                    let cannon_entity = map_query.set_tile(&mut commands, tile_pos, tile, 0, 0).unwrap();
+    let (ghost_entity, mut ghost_transform, mut ghost_sprite, mut ghost_visibility, mut orientation, mut feedback, item, cost) = match ghost.get_single_mut() {
+        Ok(t) => t,
+        Err(_) => return,
+    };
 
-                    let cannon_head = commands.spawn_bundle(SpriteBundle {
-                        texture: game_assets.cannon_green.clone(),
-                        transform: Transform {
-                            translation: vec3(ghost_transform.translation.x, ghost_transform.translation.y, 0.3),
-                            ..default()
-                        },
-                        ..default()
-                    }).insert(Cannon {
-                        species: Species::Green,
-                        cooldown: 0.0,
-                    }).id();
+    if actions.just_released(Action::Cancel) {
+        commands.entity(ghost_entity).despawn();
+        return;
+    }
 
-                    commands.entity(cannon_entity).insert(CannonBase(cannon_head));
-                }
+    ghost_visibility.is_visible = !pointer.over_ui;
+    ghost_transform.rotation = Quat::IDENTITY;
+    feedback.0 = None;
 
-                Item::BlueCannon => {
-                    let tile = Tile {
-                        texture_index: TILE_CANNON,
-                        flip_x,
-                        flip_y,
-                        flip_d,
-                        ..default()
-                    };
-                    let cannon_entity = map_query.set_tile(&mut commands, tile_pos, tile, 0, 0).unwrap();
+    if pointer.over_ui {
+        return;
+    }
 
-                    let cannon_head = commands.spawn_bundle(SpriteBundle {
-                        texture: game_assets.cannon_blue.clone(),
-                        transform: Transform {
-                            translation: vec3(ghost_transform.translation.x, ghost_transform.translation.y, 0.3),
-                            ..default()
-                        },
-                        ..default()
-                    }).insert(Cannon {
-                        species: Species::Blue,
-                        cooldown: 0.0,
-                    }).id();
+    if let Some(world_pos) = pointer.world {
+        let site = match pointer.tile {
+            Some(tile_pos) => {
+                let center = geometry.tile_center(tile_pos);
+                ghost_transform.translation.x = center.x;
+                ghost_transform.translation.y = center.y;
+
+                check_placement(tile_pos, *cost, &wallet, &map_query, &tile_query)
+            },
+            None => {
+                ghost_transform.translation.x = world_pos.x;
+                ghost_transform.translation.y = world_pos.y;
 
-                    commands.entity(cannon_entity).insert(CannonBase(cannon_head));
+                Err(PlacementError::OutOfBounds)
+            },
+        };
+
+        let site = match site {
+            Ok(site) => site,
+            Err(err) => {
+                feedback.0 = Some(err);
+                ghost_sprite.color = palette::RED;
+                ghost_sprite.color.set_a(0.75);
+
+                if actions.just_released(Action::Place) {
+                    denied.send(PlacementDenied);
                 }
+                return;
+            },
+        };
 
-                _ => (),
-            }
+        let tile_pos = site.tile_pos;
+        let side = orientation.0.rotate_among(site.connected, rotation_steps).unwrap();
+        orientation.0 = side;
+
+        ghost_sprite.color = palette::GREEN;
+
+        if let Item::Cannon(_) = item {
+            ghost_transform.rotation = side.facing_rotation();
+        }
+
+        if actions.just_released(Action::Place) && wallet.try_spend(*cost) {
+            let action = BuildAction {
+                item: *item,
+                cost: *cost,
+                tile_pos,
+                side,
+                translation: ghost_transform.translation.truncate(),
+                time: time.seconds_since_startup(),
+            };
+
+            build(&mut commands, &mut map_query, &species_table, &action);
+            spawn_placement_flash(&mut commands, action.translation, geometry.tile_size);
+            placed.send(ItemPlaced);
+            history.record(action);
 
-            map_query.notify_chunk_for_tile(tile_pos, 0u16, 0u16);
             commands.entity(ghost_entity).despawn();
         }
     }
 }
 
-pub fn update_cannons(mut cannon_query: Query<(&mut Transform, &GlobalTransform, &mut Cannon), Without<Monster>>,
+pub fn undo_build(actions: Res<ActionState>,
+                  time: Res<Time>,
+                  balance: Res<Balance>,
+                  species_table: Res<SpeciesTable>,
+                  mut history: ResMut<BuildHistory>,
+                  mut wallet: ResMut<Wallet>,
+                  mut map_query: MapQuery,
+                  tile_query: Query<&Tile>,
+                  cannon_bases: Query<&CannonBase>,
+                  mut commands: Commands)
+{
+    let now = time.seconds_since_startup();
+    let is_recent = |action: &BuildAction| now - action.time <= balance.undo_window as f64;
+
+    // Stale actions can't be undone anymore, so there is no point in keeping them
+    history.done.retain(is_recent);
+    history.undone.retain(is_recent);
+
+    if actions.just_pressed(Action::Undo) {
+        if let Some(mut action) = history.done.pop() {
+            demolish(&mut commands, &mut map_query, &cannon_bases, &action);
+            wallet.add(action.cost);
+
+            action.time = now;
+            history.undone.push(action);
+        }
+    }
+
+    if actions.just_pressed(Action::Redo) {
+        if let Some(mut action) = history.undone.pop() {
+            let placeable = check_placement(action.tile_pos, action.cost, &wallet, &map_query, &tile_query).is_ok();
+
+            if placeable && wallet.try_spend(action.cost) {
+                build(&mut commands, &mut map_query, &species_table, &action);
+
+                action.time = now;
+                history.done.push(action);
+            } else {
+                history.undone.push(action);
+            }
+        }
+    }
+}
+
+pub fn placement_message(ghost: Query<&PlacementFeedback, With<ItemPlacementGhost>>,
+                         mut message: Query<(&mut Text, &mut Style, &mut Visibility), With<PlacementMessage>>,
+                         pointer: Res<Pointer>,
+                         localization: Res<Localization>)
+{
+    let (mut text, mut style, mut visibility) = message.single_mut();
+
+    let error = ghost.get_single().ok().and_then(|feedback| feedback.0);
+
+    match (error, pointer.screen) {
+        (Some(error), Some(cursor)) => {
+            visibility.is_visible = true;
+            text.sections[0].value = localization.get(error.message_key()).to_string();
+            style.position.left = Val::Px(cursor.x + 16.0);
+            style.position.bottom = Val::Px(cursor.y + 16.0);
+        },
+        _ => visibility.is_visible = false,
+    }
+}
+
+pub fn update_cannons(mut cannon_query: Query<(Entity, &mut Transform, &GlobalTransform, &mut Cannon), Without<Monster>>,
                       mut monster_query: Query<(&GlobalTransform, &Monster)>,
                       time: Res<Time>,
                       mut commands: Commands,
-                      game_assets: Res<GameplayAssets>)
+                      mut fired: EventWriter<CannonFired>,
+                      geometry: Res<MapGeometry>,
+                      hit_stop: Res<HitStop>,
+                      species_table: Res<SpeciesTable>,
+                      balance: Res<Balance>)
 {
-    for (mut cannon_transform, cannon_glob_transform, mut cannon) in cannon_query.iter_mut() {
+    if hit_stop.active() {
+        return;
+    }
+
+    for (cannon_entity, mut cannon_transform, cannon_glob_transform, mut cannon) in cannon_query.iter_mut() {
         let mut distance = f32::MAX;
 
         for (monster_transform, monster) in monster_query.iter() {
@@ -268,17 +413,8 @@ pub fn update_cannons(mut cannon_query: Query<(&mut Transform, &GlobalTransform,
 
                 cannon.cooldown -= time.delta_seconds();
                 if cannon.cooldown <= 0.0 {
-                    let image = match cannon.species {
-                        Species::Red => game_assets.bullet_red.clone(),
-                        Species::Green => game_assets.bullet_green.clone(),
-                        Species::Blue => game_assets.bullet_blue.clone(),
-                    };
-
-                    let velocity: f32 = match cannon.species {
-                        Species::Red => 12.0,
-                        Species::Green => 18.0,
-                        Species::Blue => 29.0,
-                    };
+                    let image = species_table.get(cannon.species).bullet.clone();
+                    let velocity = balance.species(&species_table, cannon.species).bullet_speed;
 
                     let velocity = Quat::from_rotation_z(-offset.angle_between(Vec2::X)) * Vec3::X * velocity;
 
@@ -293,13 +429,19 @@ pub fn update_cannons(mut cannon_query: Query<(&mut Transform, &GlobalTransform,
                     }).insert(Bullet {
                         velocity: velocity.truncate(),
                         species: cannon.species,
-                    });
-
-                    cannon.cooldown = match cannon.species {
-                        Species::Red => 0.7,
-                        Species::Green => 0.5,
-                        Species::Blue => 0.25,
+                    }).insert(StateScoped(GameState::Gameplay));
+
+                    // Replaces the pop-in animation if it is still playing, so the scale is finished here
+                    cannon_transform.scale = Vec3::ONE;
+                    // The head may still be pushed back from the previous shot
+                    if let Some(tile_pos) = geometry.tile_at(cannon_glob_transform.translation.truncate()) {
+                        let rest = geometry.tile_center(tile_pos).extend(cannon_transform.translation.z);
+                        commands.entity(cannon_entity).insert(recoil(rest, cannon_transform.rotation));
                     }
+
+                    fired.send(CannonFired { species: cannon.species });
+
+                    cannon.cooldown = balance.species(&species_table, cannon.species).fire_cooldown;
                 }
             }
         }
@@ -311,33 +453,25 @@ pub fn update_cannons(mut cannon_query: Query<(&mut Transform, &GlobalTransform,
 }
 
 pub fn spawn_monsters(mut commands: Commands,
-                      mut cooldown: ResMut<MonsterSpawnCooldown>,
-                      game_assets: Res<GameplayAssets>,
+                      mut schedule: ResMut<SpawnSchedule>,
+                      species_table: Res<SpeciesTable>,
+                      balance: Res<Balance>,
+                      hit_stop: Res<HitStop>,
                       time: Res<Time>)
 {
-    cooldown.0 -= time.delta_seconds();
+    if hit_stop.active() {
+        return;
+    }
 
-    if cooldown.0 <= 0.0 {
-        cooldown.0 = 5.0 + random::<f32>() * 10.0;
+    schedule.cooldown -= time.delta_seconds();
 
-        let distance = 24.0 * 10.0;
-        let angle = random::<f32>() * PI * 2.0;
-        let kind = random::<f32>();
-        let mut species = Species::Red;
-        if kind >= 1.0 / 3.0 && kind < 2.0 / 3.0 {
-            species = Species::Green;
-        }
-        if kind >= 2.0 / 3.0 {
-            species = Species::Blue;
-        }
+    if schedule.cooldown <= 0.0 {
+        let spawn = schedule.pop(&balance, species_table.len());
 
-        let image = match species {
-            Species::Red => game_assets.monster_red.clone(),
-            Species::Green => game_assets.monster_green.clone(),
-            Species::Blue => game_assets.monster_blue.clone(),
-        };
+        let distance = 24.0 * 10.0;
+        let image = species_table.get(spawn.species).monster.clone();
 
-        let position = Quat::from_rotation_z(angle) * vec3(distance, 0.0, 3.7);
+        let position = Quat::from_rotation_z(spawn.angle) * vec3(distance, 0.0, 3.7);
 
         commands.spawn_bundle(SpriteBundle {
             texture: image,
@@ -346,59 +480,200 @@ pub fn spawn_monsters(mut commands: Commands,
                 ..default()
             },
             ..default()
-        }).insert(Monster(species))
-            .insert(Health(cooldown.1));
+        }).insert(Monster(spawn.species))
+            .insert(Health(spawn.health))
+            .insert(MaxHealth(spawn.health))
+            .insert(StateScoped(GameState::Gameplay))
+            .with_children(|monster| {
+                spawn_health_bar(monster, species_table.get(spawn.species).color);
+            });
+    }
+}
+
+const HEALTH_BAR_SIZE: Vec2 = const_vec2!([16.0, 2.0]);
+
+/// Spawns a hidden health bar above the parent sprite.
+fn spawn_health_bar(parent: &mut ChildBuilder, color: Color) {
+    parent.spawn_bundle(SpriteBundle {
+        sprite: Sprite {
+            color: palette::BLACK,
+            custom_size: Some(HEALTH_BAR_SIZE + Vec2::splat(2.0)),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 14.0, 0.1),
+        visibility: Visibility { is_visible: false },
+        ..default()
+    }).insert(HealthBar).with_children(|bar| {
+        bar.spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(HEALTH_BAR_SIZE),
+                anchor: Anchor::CenterLeft,
+                ..default()
+            },
+            transform: Transform::from_xyz(-HEALTH_BAR_SIZE.x / 2.0, 0.0, 0.1),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        }).insert(HealthBarFill);
+    });
+}
 
-        cooldown.1 += 1;
+/// Shows the health bars of damaged entities.
+pub fn update_health_bars(damaged: Query<(&Health, &MaxHealth, &Children), Changed<Health>>,
+                          mut bars: Query<(&mut Visibility, &Children), (With<HealthBar>, Without<HealthBarFill>)>,
+                          mut fills: Query<(&mut Visibility, &mut Sprite), With<HealthBarFill>>)
+{
+    for (health, max_health, children) in damaged.iter() {
+        let fraction = (health.0 as f32 / max_health.0 as f32).clamp(0.0, 1.0);
+        // Visibility is not inherited, so the fill has to be shown separately
+        let is_visible = health.0 < max_health.0;
+
+        for child in children.iter() {
+            let (mut bar_visibility, bar_children) = match bars.get_mut(*child) {
+                Ok(bar) => bar,
+                Err(_) => continue,
+            };
+            bar_visibility.is_visible = is_visible;
+
+            for fill_entity in bar_children.iter() {
+                if let Ok((mut fill_visibility, mut fill_sprite)) = fills.get_mut(*fill_entity) {
+                    fill_visibility.is_visible = is_visible;
+                    fill_sprite.custom_size = Some(vec2(HEALTH_BAR_SIZE.x * fraction, HEALTH_BAR_SIZE.y));
+                }
+            }
+        }
     }
 }
 
+/// Seconds between a monster reaching the core and the lose screen, so the camera shake plays out.
+const DEFEAT_DELAY: f32 = 0.75;
+
 pub fn move_monsters(mut monsters: Query<(&mut Transform, &Monster)>,
                      time: Res<Time>,
-                     mut state: ResMut<State<GameState>>)
+                     hit_stop: Res<HitStop>,
+                     species_table: Res<SpeciesTable>,
+                     balance: Res<Balance>,
+                     mut core_destroyed: EventWriter<CoreDestroyed>,
+                     mut defeat: ResMut<DefeatCountdown>)
 {
+    if hit_stop.active() || defeat.0.is_some() {
+        return;
+    }
+
     for (mut transform, monster) in monsters.iter_mut() {
         let dir = -transform.translation.normalize().truncate().extend(0.0);
-        transform.translation += dir * match monster.0 {
-            Species::Red => 27.0 * time.delta_seconds(),
-            Species::Green => 14.0 * time.delta_seconds(),
-            Species::Blue => 9.0 * time.delta_seconds(),
-        };
+        transform.translation += dir * balance.species(&species_table, monster.0).speed * time.delta_seconds();
 
         if transform.translation.length() <= 24.0 {
-            state.set(GameState::Lose).unwrap();
+            core_destroyed.send(CoreDestroyed);
+            defeat.0 = Some(DEFEAT_DELAY);
+            return;
         }
     }
 }
 
+/// Loses the game once the camera had the time to shake from the core being reached.
+pub fn count_down_defeat(mut defeat: ResMut<DefeatCountdown>,
+                         time: Res<Time>,
+                         mut state: ResMut<State<GameState>>)
+{
+    let remaining = match &mut defeat.0 {
+        Some(remaining) => remaining,
+        None => return,
+    };
+
+    *remaining -= time.delta_seconds();
+
+    if *remaining <= 0.0 {
+        // Wins over pausing in the same frame
+        state.overwrite_set(GameState::Lose).unwrap();
+    }
+}
+
 pub fn move_bullets(mut commands: Commands,
                     mut bullets: Query<(Entity, &mut Transform, &Bullet)>,
-                    mut monsters: Query<(Entity, &mut Transform, &Monster, &mut Health), Without<Bullet>>,
-                    mut wallet: ResMut<Wallet>)
+                    mut monsters: Query<(Entity, &mut Transform, &Monster, &mut Health, &MaxHealth), Without<Bullet>>,
+                    mut hits: EventWriter<MonsterHit>,
+                    mut kills: EventWriter<MonsterKilled>,
+                    mut wallet: ResMut<Wallet>,
+                    hit_stop: Res<HitStop>,
+                    species_table: Res<SpeciesTable>,
+                    balance: Res<Balance>)
 {
+    if hit_stop.active() {
+        return;
+    }
+
     for (bullet_entity, mut bullet_transform, bullet) in bullets.iter_mut() {
         bullet_transform.translation += bullet.velocity.extend(0.0);
 
-        for (monster_entity, monster_transform, monster, mut health) in monsters.iter_mut() {
+        for (monster_entity, monster_transform, monster, mut health, max_health) in monsters.iter_mut() {
+            // Killed monsters are only removed at the end of the frame
+            if health.0 <= 0 {
+                continue;
+            }
+
             if monster.0 == bullet.species && monster_transform.translation.distance(bullet_transform.translation) < 30.0 {
                 commands.entity(bullet_entity).despawn();
 
-                health.0 -= match monster.0 {
-                    Species::Red => 3,
-                    Species::Green => 2,
-                    Species::Blue => 1,
-                };
+                let damage = balance.species(&species_table, monster.0).damage;
+                health.0 -= damage;
 
-                if health.0 <= 0 {
-                    commands.entity(monster_entity).despawn();
+                let position = monster_transform.translation.truncate();
+                hits.send(MonsterHit {
+                    species: monster.0,
+                    damage,
+                    position,
+                });
 
-                    match monster.0 {
-                        Species::Red => wallet.red_squares += 5,
-                        Species::Green => wallet.green_triangles += 5,
-                        Species::Blue => wallet.blue_circles += 5,
-                    };
+                if health.0 <= 0 {
+                    die(&mut commands, monster_entity);
+
+                    wallet.add(Money::new(monster.0, balance.kill_bounty));
+                    kills.send(MonsterKilled {
+                        species: monster.0,
+                        bounty: balance.kill_bounty,
+                        max_health: max_health.0,
+                        position,
+                    });
                 }
             }
         }
     }
+}
+
+pub fn reload_balance(mut events: EventReader<AssetEvent<Balance>>,
+                      balances: Res<Assets<Balance>>,
+                      game_assets: Res<GameplayAssets>,
+                      species_table: Res<SpeciesTable>,
+                      mut balance: ResMut<Balance>,
+                      mut buttons: Query<&mut BuyItemButton>)
+{
+    for event in events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            if *handle != game_assets.balance {
+                continue;
+            }
+
+            let new_balance = match balances.get(handle) {
+                Some(new_balance) => new_balance,
+                None => continue,
+            };
+
+            match new_balance.validate(&species_table) {
+                Ok(()) => {
+                    *balance = new_balance.clone();
+
+                    for mut button in buttons.iter_mut() {
+                        if let Some(cost) = balance.item_cost(&species_table, button.item) {
+                            button.cost = cost;
+                        }
+                    }
+
+                    info!("Balance reloaded");
+                },
+                Err(err) => error!("Ignoring the reloaded balance: {}", err),
+            }
+        }
+    }
 }
\ No newline at end of file
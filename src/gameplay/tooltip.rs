@@ -0,0 +1,161 @@
+use bevy::math::vec3;
+use bevy::prelude::*;
+use crate::{GameState, PreloadedAssets};
+use crate::cleanup::StateScoped;
+use crate::localization::Localization;
+use crate::theme::{spawn_panel, Skin, Theme};
+use crate::gameplay::balance::Balance;
+use crate::gameplay::components::*;
+use crate::gameplay::pointer::Pointer;
+use crate::gameplay::species::SpeciesTable;
+
+/// Panel around the tooltip text, hidden from the layout while nothing is hovered.
+#[derive(Component)]
+pub struct ItemTooltip;
+
+#[derive(Component)]
+pub struct ItemTooltipText;
+
+pub fn spawn_tooltip(commands: &mut Commands, theme: &Theme) {
+    let text = commands.spawn_bundle(TextBundle {
+        transform: Transform {
+            scale: vec3(0.25, 0.25, 1.0) * 2.0,
+            ..default()
+        },
+        visibility: Visibility { is_visible: false },
+        ..default()
+    }).insert(ItemTooltipText).id();
+
+    let panel = spawn_panel(commands, theme, Skin::Tooltip, Style {
+        position_type: PositionType::Absolute,
+        display: Display::None,
+        ..default()
+    }, text);
+    commands.entity(panel)
+        .insert(ItemTooltip)
+        .insert(StateScoped(GameState::Gameplay));
+}
+
+/// One line of a tooltip, in the given colour.
+type TooltipLine = (String, Color);
+
+/// Describes an item using the same numbers the gameplay systems use.
+pub fn item_tooltip(item: Item,
+                    cost: Money,
+                    species_table: &SpeciesTable,
+                    balance: &Balance,
+                    localization: &Localization,
+                    theme: &Theme) -> Vec<TooltipLine> {
+    let cost_info = species_table.get(cost.species);
+
+    let mut lines = Vec::new();
+
+    match item {
+        Item::Connector => {
+            lines.push((localization.get("tooltip.connector").to_string(), theme.text));
+            lines.push((localization.get("tooltip.connector_description").to_string(), theme.text));
+        },
+        Item::Cannon(species) => {
+            let info = species_table.get(species);
+            let stats = balance.species(species_table, species);
+            let species_name = species_display_name(&info.name, localization);
+
+            let fire_rate = format!("{:.1}", 1.0 / stats.fire_cooldown);
+
+            lines.push((localization.format("tooltip.cannon", &[("species", &species_name)]), info.color));
+            lines.push((localization.get("tooltip.cannon_description").to_string(), theme.text));
+            lines.push((localization.format("tooltip.damage", &[("damage", &stats.damage)]), theme.text));
+            lines.push((localization.format("tooltip.fire_rate", &[("rate", &fire_rate)]), theme.text));
+            lines.push((localization.get("tooltip.range").to_string(), theme.text));
+            lines.push((localization.format("tooltip.targets", &[("species", &species_name)]), info.color));
+        },
+        Item::Converter { from, to } => {
+            let from_name = species_display_name(&species_table.get(from).name, localization);
+            let to_name = species_display_name(&species_table.get(to).name, localization);
+
+            lines.push((localization.format("tooltip.converter", &[("from", &from_name), ("to", &to_name)]), theme.text));
+        },
+    }
+
+    let cost_name = species_display_name(&cost_info.name, localization);
+    lines.push((localization.format("tooltip.cost", &[("amount", &cost.amount), ("species", &cost_name)]), cost_info.color));
+
+    lines
+}
+
+/// Species names are lowercase identifiers in the data files, species without a translation show them capitalized.
+fn species_display_name(name: &str, localization: &Localization) -> String {
+    if let Some(translated) = localization.try_get(&format!("species.{}", name)) {
+        return translated.to_string();
+    }
+
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+pub fn show_tooltip(buttons: Query<(Entity, &Interaction, &BuyItemButton)>,
+                    mut tooltip: Query<&mut Style, With<ItemTooltip>>,
+                    mut tooltip_text: Query<(&mut Text, &mut Visibility), With<ItemTooltipText>>,
+                    pointer: Res<Pointer>,
+                    species_table: Res<SpeciesTable>,
+                    balance: Res<Balance>,
+                    localization: Res<Localization>,
+                    theme: Res<Theme>,
+                    pre_assets: Res<PreloadedAssets>,
+                    mut shown_button: Local<Option<Entity>>)
+{
+    let mut style = tooltip.single_mut();
+    let (mut text, mut visibility) = tooltip_text.single_mut();
+
+    let hovered = buttons.iter().find(|(_, interaction, _)| **interaction != Interaction::None);
+
+    let (button_entity, button, cursor) = match (hovered, pointer.screen) {
+        (Some((entity, _, button)), Some(cursor)) => (entity, button, cursor),
+        _ => {
+            *shown_button = None;
+
+            // Visibility isn't inherited, the panel is taken out of the layout instead
+            if style.display != Display::None {
+                style.display = Display::None;
+            }
+            if visibility.is_visible {
+                visibility.is_visible = false;
+            }
+            return;
+        },
+    };
+
+    // Setting the text makes it lay out again, so it is only rebuilt when what it shows changed
+    if *shown_button != Some(button_entity) || balance.is_changed() || localization.is_changed() {
+        *shown_button = Some(button_entity);
+
+        let lines = item_tooltip(button.item, button.cost, &species_table, &balance, &localization, &theme);
+        let line_count = lines.len();
+
+        text.sections = lines.into_iter().enumerate().map(|(i, (line, color))| TextSection {
+            value: if i + 1 < line_count { line + "\n" } else { line },
+            style: TextStyle {
+                font: pre_assets.font.clone(),
+                font_size: 56.0,
+                color,
+            },
+        }).collect();
+    }
+
+    if !visibility.is_visible {
+        visibility.is_visible = true;
+    }
+
+    let left = Val::Px(cursor.x + 16.0);
+    let bottom = Val::Px(cursor.y + 16.0);
+
+    if style.display != Display::Flex || style.position.left != left || style.position.bottom != bottom {
+        style.display = Display::Flex;
+        style.position.left = left;
+        style.position.bottom = bottom;
+    }
+}
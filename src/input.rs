@@ -0,0 +1,493 @@
+use bevy::input::mouse::MouseWheel;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use serde::{Deserialize, Serialize};
+use crate::config;
+use crate::localization::Localization;
+
+const BINDINGS_FILE: &str = "bindings.ron";
+
+/// Something the player wants to do, independent of the button used to do it.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
+    Place,
+    Cancel,
+    RotateClockwise,
+    RotateCounterClockwise,
+    NextItem,
+    PreviousItem,
+    SelectItem(usize),
+    Undo,
+    Redo,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    /// Pans the view along with the mouse while held.
+    DragPan,
+    ZoomIn,
+    ZoomOut,
+    Pause,
+}
+
+/// Number of [`Action::SelectItem`] slots.
+pub const ITEM_SLOTS: usize = 9;
+
+impl Action {
+    /// All actions in the order they are shown on the controls screen.
+    pub fn all() -> Vec<Action> {
+        let mut actions = vec![
+            Action::Place,
+            Action::Cancel,
+            Action::RotateClockwise,
+            Action::RotateCounterClockwise,
+            Action::NextItem,
+            Action::PreviousItem,
+        ];
+        actions.extend((0..ITEM_SLOTS).map(Action::SelectItem));
+        actions.extend([
+            Action::Undo,
+            Action::Redo,
+            Action::PanUp,
+            Action::PanDown,
+            Action::PanLeft,
+            Action::PanRight,
+            Action::DragPan,
+            Action::ZoomIn,
+            Action::ZoomOut,
+            Action::Pause,
+        ]);
+        actions
+    }
+
+    pub fn name(self, localization: &Localization) -> String {
+        let key = match self {
+            Action::Place => "action.place",
+            Action::Cancel => "action.cancel",
+            Action::RotateClockwise => "action.rotate_clockwise",
+            Action::RotateCounterClockwise => "action.rotate_counter_clockwise",
+            Action::NextItem => "action.next_item",
+            Action::PreviousItem => "action.previous_item",
+            Action::SelectItem(slot) => return localization.format("action.select_item", &[("slot", &(slot + 1))]),
+            Action::Undo => "action.undo",
+            Action::Redo => "action.redo",
+            Action::PanUp => "action.pan_up",
+            Action::PanDown => "action.pan_down",
+            Action::PanLeft => "action.pan_left",
+            Action::PanRight => "action.pan_right",
+            Action::DragPan => "action.drag_pan",
+            Action::ZoomIn => "action.zoom_in",
+            Action::ZoomOut => "action.zoom_out",
+            Action::Pause => "action.pause",
+        };
+
+        localization.get(key).to_string()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum InputButton {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    WheelUp,
+    WheelDown,
+    /// Button of any connected gamepad.
+    Gamepad(GamepadButtonType),
+}
+
+/// A button, possibly combined with modifier keys.
+///
+/// The modifiers have to match exactly when the button is pressed, so e.g. `Ctrl+Z` doesn't trigger the actions
+/// bound to `Z`. Once pressed, the binding stays held until the button is released, whatever the modifiers do.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct Binding {
+    pub button: InputButton,
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub shift: bool,
+}
+
+impl Binding {
+    pub fn new(button: InputButton) -> Binding {
+        Binding {
+            button,
+            ctrl: false,
+            shift: false,
+        }
+    }
+
+    pub fn key(key: KeyCode) -> Binding {
+        Binding::new(InputButton::Key(key))
+    }
+
+    pub fn mouse(button: MouseButton) -> Binding {
+        Binding::new(InputButton::Mouse(button))
+    }
+
+    pub fn gamepad(button: GamepadButtonType) -> Binding {
+        Binding::new(InputButton::Gamepad(button))
+    }
+
+    pub fn with_ctrl(self) -> Binding {
+        Binding {
+            ctrl: true,
+            ..self
+        }
+    }
+
+    pub fn with_shift(self) -> Binding {
+        Binding {
+            shift: true,
+            ..self
+        }
+    }
+
+    pub fn name(&self, localization: &Localization) -> String {
+        let mut name = match self.button {
+            InputButton::Key(key) => key_name(key),
+            InputButton::Mouse(MouseButton::Left) => localization.get("binding.mouse_left").to_string(),
+            InputButton::Mouse(MouseButton::Right) => localization.get("binding.mouse_right").to_string(),
+            InputButton::Mouse(MouseButton::Middle) => localization.get("binding.mouse_middle").to_string(),
+            InputButton::Mouse(MouseButton::Other(button)) => localization.format("binding.mouse_other", &[("button", &button)]),
+            InputButton::WheelUp => localization.get("binding.wheel_up").to_string(),
+            InputButton::WheelDown => localization.get("binding.wheel_down").to_string(),
+            InputButton::Gamepad(button) => localization.format("binding.gamepad", &[("button", &format!("{:?}", button))]),
+        };
+
+        if self.shift {
+            name = localization.format("binding.shift", &[("button", &name)]);
+        }
+        if self.ctrl {
+            name = localization.format("binding.ctrl", &[("button", &name)]);
+        }
+
+        name
+    }
+}
+
+/// What is printed on the key, for the keys where that is not their key code.
+fn key_name(key: KeyCode) -> String {
+    use KeyCode::*;
+
+    let printed = match key {
+        Key1 | Numpad1 => "1",
+        Key2 | Numpad2 => "2",
+        Key3 | Numpad3 => "3",
+        Key4 | Numpad4 => "4",
+        Key5 | Numpad5 => "5",
+        Key6 | Numpad6 => "6",
+        Key7 | Numpad7 => "7",
+        Key8 | Numpad8 => "8",
+        Key9 | Numpad9 => "9",
+        Key0 | Numpad0 => "0",
+        Minus | NumpadSubtract => "-",
+        Equals | NumpadEquals => "=",
+        Plus | NumpadAdd => "+",
+        Asterisk | NumpadMultiply => "*",
+        Slash | NumpadDivide => "/",
+        Period | NumpadDecimal => ".",
+        Comma | NumpadComma => ",",
+        Backslash => "\\",
+        Semicolon => ";",
+        Colon => ":",
+        Apostrophe => "'",
+        Grave => "`",
+        LBracket => "[",
+        RBracket => "]",
+        At => "@",
+        Caret => "^",
+        Underline => "_",
+        LControl | RControl => "Ctrl",
+        LShift | RShift => "Shift",
+        LAlt | RAlt => "Alt",
+        Return | NumpadEnter => "Enter",
+        Back => "Backspace",
+        Escape => "Esc",
+        _ => return format!("{:?}", key),
+    };
+
+    printed.to_string()
+}
+
+/// Buttons bound to each action. An action can have several bindings.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bindings(pub Vec<(Action, Binding)>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use Action::*;
+
+        let mut bindings = vec![
+            (Place, Binding::mouse(MouseButton::Left)),
+            (Cancel, Binding::mouse(MouseButton::Right)),
+            (Cancel, Binding::key(KeyCode::Escape)),
+            (RotateClockwise, Binding::key(KeyCode::R)),
+            (RotateClockwise, Binding::new(InputButton::WheelUp)),
+            (RotateCounterClockwise, Binding::key(KeyCode::R).with_shift()),
+            (RotateCounterClockwise, Binding::new(InputButton::WheelDown)),
+            (NextItem, Binding::key(KeyCode::E)),
+            (NextItem, Binding::key(KeyCode::Tab)),
+            (PreviousItem, Binding::key(KeyCode::Q)),
+            (PreviousItem, Binding::key(KeyCode::Tab).with_shift()),
+            (Undo, Binding::key(KeyCode::Z).with_ctrl()),
+            (Redo, Binding::key(KeyCode::Y).with_ctrl()),
+            (Redo, Binding::key(KeyCode::Z).with_ctrl().with_shift()),
+            (PanUp, Binding::key(KeyCode::W)),
+            (PanUp, Binding::key(KeyCode::Up)),
+            (PanDown, Binding::key(KeyCode::S)),
+            (PanDown, Binding::key(KeyCode::Down)),
+            (PanLeft, Binding::key(KeyCode::A)),
+            (PanLeft, Binding::key(KeyCode::Left)),
+            (PanRight, Binding::key(KeyCode::D)),
+            (PanRight, Binding::key(KeyCode::Right)),
+            (DragPan, Binding::mouse(MouseButton::Middle)),
+            (ZoomIn, Binding::new(InputButton::WheelUp)),
+            (ZoomIn, Binding::key(KeyCode::Equals)),
+            (ZoomOut, Binding::new(InputButton::WheelDown)),
+            (ZoomOut, Binding::key(KeyCode::Minus)),
+            (Pause, Binding::key(KeyCode::P)),
+            (Place, Binding::gamepad(GamepadButtonType::South)),
+            (Cancel, Binding::gamepad(GamepadButtonType::East)),
+            (RotateClockwise, Binding::gamepad(GamepadButtonType::North)),
+            (RotateCounterClockwise, Binding::gamepad(GamepadButtonType::West)),
+            (NextItem, Binding::gamepad(GamepadButtonType::RightTrigger)),
+            (PreviousItem, Binding::gamepad(GamepadButtonType::LeftTrigger)),
+            (Pause, Binding::gamepad(GamepadButtonType::Start)),
+        ];
+
+        let digits = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+        ];
+        for (slot, key) in digits.into_iter().enumerate() {
+            bindings.push((SelectItem(slot), Binding::key(key)));
+        }
+
+        Bindings(bindings)
+    }
+}
+
+impl Bindings {
+    pub fn load() -> Bindings {
+        let mut bindings: Bindings = config::load(BINDINGS_FILE).unwrap_or_default();
+
+        // Actions added since the bindings were saved get their default buttons
+        let missing: Vec<(Action, Binding)> = Bindings::default().0.into_iter()
+            .filter(|(action, _)| bindings.of(*action).next().is_none())
+            .collect();
+        bindings.0.extend(missing);
+
+        bindings
+    }
+
+    pub fn save(&self) {
+        config::save(BINDINGS_FILE, self);
+    }
+
+    pub fn of(&self, action: Action) -> impl Iterator<Item = &Binding> {
+        self.0.iter()
+            .filter(move |(bound_action, _)| *bound_action == action)
+            .map(|(_, binding)| binding)
+    }
+
+    /// Names of all bindings of the action, for showing in the UI.
+    pub fn names(&self, action: Action, localization: &Localization) -> String {
+        let names: Vec<String> = self.of(action).map(|binding| binding.name(localization)).collect();
+
+        if names.is_empty() {
+            localization.get("binding.none").to_string()
+        } else {
+            names.join(", ")
+        }
+    }
+
+    /// Replaces all bindings of the action with a single one.
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        self.0.retain(|(bound_action, _)| *bound_action != action);
+        self.0.push((action, binding));
+    }
+}
+
+/// State of all actions in the current frame, derived from the raw input and the [`Bindings`].
+#[derive(Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+    /// Bindings whose button was pressed with the right modifiers and is still held.
+    held: HashSet<(Action, Binding)>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    /// Moves on to the next frame, given whether the button of each binding is down and whether it just went down,
+    /// and which modifiers are held.
+    fn update(&mut self, buttons: impl Iterator<Item = (Action, Binding, bool, bool)>, ctrl: bool, shift: bool) {
+        let previous = std::mem::take(&mut self.pressed);
+        let previously_held = std::mem::take(&mut self.held);
+        self.just_pressed.clear();
+        self.just_released.clear();
+
+        for (action, binding, down, just_down) in buttons {
+            // Modifiers only matter when the button goes down, so e.g. pressing Shift while dragging doesn't drop the item
+            let held = down && previously_held.contains(&(action, binding));
+            let pressed = held || (just_down && binding.ctrl == ctrl && binding.shift == shift);
+
+            if !pressed {
+                continue;
+            }
+
+            self.pressed.insert(action);
+            if down {
+                self.held.insert((action, binding));
+            } else {
+                self.just_pressed.insert(action);
+            }
+        }
+
+        for action in self.pressed.clone() {
+            if !previous.contains(&action) {
+                self.just_pressed.insert(action);
+            }
+        }
+
+        for action in previous {
+            if !self.pressed.contains(&action) {
+                self.just_released.insert(action);
+            }
+        }
+    }
+}
+
+pub fn register_systems(app: &mut App) {
+    app.insert_resource(Bindings::load())
+        .init_resource::<ActionState>()
+        .add_system_to_stage(CoreStage::PreUpdate, update_actions.after(InputSystem));
+}
+
+fn update_actions(mut state: ResMut<ActionState>,
+                  bindings: Res<Bindings>,
+                  keys: Res<Input<KeyCode>>,
+                  mouse_buttons: Res<Input<MouseButton>>,
+                  gamepads: Res<Gamepads>,
+                  gamepad_buttons: Res<Input<GamepadButton>>,
+                  mut mouse_wheel: EventReader<MouseWheel>)
+{
+    let mut wheel_up = false;
+    let mut wheel_down = false;
+    for event in mouse_wheel.iter() {
+        wheel_up |= event.y > 0.0;
+        wheel_down |= event.y < 0.0;
+    }
+
+    let ctrl = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    let shift = keys.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+
+    let buttons = bindings.0.iter().map(|(action, binding)| {
+        let (down, just_down) = match binding.button {
+            InputButton::Key(key) => (keys.pressed(key), keys.just_pressed(key)),
+            InputButton::Mouse(button) => (mouse_buttons.pressed(button), mouse_buttons.just_pressed(button)),
+            InputButton::Gamepad(button) => (
+                gamepads.iter().any(|gamepad| gamepad_buttons.pressed(GamepadButton(*gamepad, button))),
+                gamepads.iter().any(|gamepad| gamepad_buttons.just_pressed(GamepadButton(*gamepad, button))),
+            ),
+            // Every scroll counts as a separate press, the wheel is never held down
+            InputButton::WheelUp => (false, wheel_up),
+            InputButton::WheelDown => (false, wheel_down),
+        };
+
+        (*action, *binding, down, just_down)
+    });
+
+    state.update(buttons, ctrl, shift);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(state: &mut ActionState, buttons: &[(Action, Binding, bool, bool)], ctrl: bool, shift: bool) {
+        state.update(buttons.iter().copied(), ctrl, shift);
+    }
+
+    #[test]
+    fn modifiers_pick_the_binding_when_the_button_goes_down() {
+        let next = Binding::key(KeyCode::Tab);
+        let previous = Binding::key(KeyCode::Tab).with_shift();
+        let mut state = ActionState::default();
+
+        frame(&mut state, &[(Action::NextItem, next, true, true), (Action::PreviousItem, previous, true, true)], false, true);
+
+        assert!(state.just_pressed(Action::PreviousItem));
+        assert!(!state.pressed(Action::NextItem));
+
+        frame(&mut state, &[(Action::NextItem, next, false, false), (Action::PreviousItem, previous, false, false)], false, true);
+        frame(&mut state, &[(Action::NextItem, next, true, true), (Action::PreviousItem, previous, true, true)], true, true);
+
+        assert!(!state.pressed(Action::NextItem));
+        assert!(!state.pressed(Action::PreviousItem));
+    }
+
+    #[test]
+    fn held_binding_ignores_modifiers_until_released() {
+        let place = Binding::mouse(MouseButton::Left);
+        let mut state = ActionState::default();
+
+        frame(&mut state, &[(Action::Place, place, true, true)], false, false);
+        assert!(state.just_pressed(Action::Place));
+
+        frame(&mut state, &[(Action::Place, place, true, false)], false, true);
+        assert!(state.pressed(Action::Place));
+        assert!(!state.just_pressed(Action::Place));
+        assert!(!state.just_released(Action::Place));
+
+        frame(&mut state, &[(Action::Place, place, false, false)], false, true);
+        assert!(!state.pressed(Action::Place));
+        assert!(state.just_released(Action::Place));
+    }
+
+    #[test]
+    fn button_going_down_with_wrong_modifiers_is_not_held() {
+        let place = Binding::mouse(MouseButton::Left);
+        let mut state = ActionState::default();
+
+        frame(&mut state, &[(Action::Place, place, true, true)], true, false);
+        frame(&mut state, &[(Action::Place, place, true, false)], false, false);
+
+        assert!(!state.pressed(Action::Place));
+    }
+
+    #[test]
+    fn wheel_presses_last_a_single_frame() {
+        let wheel = Binding::new(InputButton::WheelUp);
+        let mut state = ActionState::default();
+
+        frame(&mut state, &[(Action::ZoomIn, wheel, false, true)], false, false);
+        assert!(state.pressed(Action::ZoomIn));
+        assert!(state.just_pressed(Action::ZoomIn));
+
+        frame(&mut state, &[(Action::ZoomIn, wheel, false, false)], false, false);
+        assert!(!state.pressed(Action::ZoomIn));
+        assert!(state.just_released(Action::ZoomIn));
+    }
+}
@@ -2,10 +2,19 @@
 mod web_main;
 
 mod palette;
+mod config;
+mod cleanup;
+mod input;
+mod pause;
+mod settings;
+mod localization;
+mod theme;
 mod loading;
 mod assets;
+mod audio;
 mod game;
 mod gameplay;
 mod lose;
+mod music;
 
 pub use game::*;
@@ -1,26 +1,56 @@
 use bevy::math::vec3;
 use bevy::prelude::*;
 use bevy::ui::Val::Percent;
-use crate::{palette, PreloadedAssets};
+use bevy::asset::{HandleId, LoadState};
+use bevy::math::const_vec2;
+use bevy_asset_loader::AssetCollection;
+use crate::{GameState, PreloadedAssets};
+use crate::assets::{AudioAssets, GameplayAssets, MusicAssets};
+use crate::cleanup::StateScoped;
+use crate::gameplay::species::SpeciesTable;
+use crate::localization::{Localization, LocalizedText};
+use crate::theme::Theme;
 
-pub fn loading_start(mut commands: Commands, assets: Res<PreloadedAssets>) {
+const PROGRESS_BAR_SIZE: Vec2 = const_vec2!([240.0, 12.0]);
+
+#[derive(Component)]
+pub struct LoadingProgressFill;
+
+#[derive(Component)]
+pub struct LoadingProgressCounter;
+
+/// Everything loaded before gameplay starts, used to show the loading progress.
+///
+/// Whatever the species tables refer to is only known once they are loaded, so it isn't in here.
+/// The font is embedded in the game and added before anything else, so there is nothing to wait for.
+pub struct TrackedAssets(Vec<HandleUntyped>);
+
+/// The asset which failed to load, or loaded but can't be used.
+pub struct LoadingFailure {
+    pub path: String,
+    /// Why a loaded asset can't be used.
+    pub reason: Option<String>,
+}
+
+pub fn loading_start(mut commands: Commands, assets: Res<PreloadedAssets>, theme: Res<Theme>) {
     let mut camera = UiCameraBundle::default();
-    commands.spawn_bundle(camera);
+    commands.spawn_bundle(camera).insert(StateScoped(GameState::LoadingAssets));
 
     commands.spawn_bundle(NodeBundle {
         style: Style {
             size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
             align_items: AlignItems::Center,
             justify_content: JustifyContent::Center,
             ..default()
         },
         color: Color::NONE.into(),
         ..default()
-    }).with_children(|parent| {
+    }).insert(StateScoped(GameState::LoadingAssets)).with_children(|parent| {
         parent.spawn_bundle(TextBundle {
-            text: Text::with_section("Loading assets...", TextStyle {
+            text: Text::with_section("", TextStyle {
                 font: assets.font.clone(),
-                color: palette::WHITE,
+                color: theme.text,
                 font_size: 56.0,
             }, TextAlignment {
                 horizontal: HorizontalAlign::Center,
@@ -31,12 +61,143 @@ pub fn loading_start(mut commands: Commands, assets: Res<PreloadedAssets>) {
                 ..default()
             },
             ..default()
+        }).insert(LocalizedText("loading"));
+
+        parent.spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(PROGRESS_BAR_SIZE.x), Val::Px(PROGRESS_BAR_SIZE.y)),
+                margin: Rect::all(Val::Px(12.0)),
+                ..default()
+            },
+            color: theme.track.into(),
+            ..default()
+        }).with_children(|bar| {
+            bar.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                    ..default()
+                },
+                color: theme.fill.into(),
+                ..default()
+            }).insert(LoadingProgressFill);
         });
+
+        parent.spawn_bundle(TextBundle {
+            text: Text::with_section("", TextStyle {
+                font: assets.font.clone(),
+                color: theme.text,
+                font_size: 56.0,
+            }, default()),
+            transform: Transform {
+                scale: vec3(0.25, 0.25, 1.0) * 2.0,
+                ..default()
+            },
+            ..default()
+        }).insert(LoadingProgressCounter);
     });
 }
 
-pub fn loading_end(mut commands: Commands, mut entities: Query<Entity>) {
-    for entity in entities.iter_mut() {
-        commands.entity(entity).despawn();
+/// Collects the handles of the asset collections, the theme and the string tables.
+///
+/// Loading a collection again only gives back the handles of the assets the asset loader is already loading.
+pub fn track_assets(world: &mut World) {
+    let mut handles = GameplayAssets::load(world);
+    handles.extend(AudioAssets::load(world));
+    handles.extend(MusicAssets::load(world));
+
+    let theme = world.resource::<Theme>();
+    handles.extend([theme.panel.clone_untyped(), theme.tooltip.clone_untyped(), theme.dialog.clone_untyped()]);
+
+    let localization = world.resource::<Localization>();
+    handles.extend(localization.tables().map(|table| table.clone_untyped()));
+
+    world.insert_resource(TrackedAssets(handles));
+}
+
+/// Path of the asset a handle points to, for telling the player which one is broken.
+pub fn asset_path(asset_server: &AssetServer, handle: impl Into<HandleId>) -> String {
+    asset_server.get_handle_path(handle)
+        .map_or_else(String::new, |path| path.path().display().to_string())
+}
+
+/// Fills the progress bar as the assets finish loading, and gives up on the first one which fails.
+pub fn loading_progress(asset_server: Res<AssetServer>,
+                        tracked: Res<TrackedAssets>,
+                        species_tables: Res<Assets<SpeciesTable>>,
+                        localization: Res<Localization>,
+                        mut fills: Query<&mut Style, With<LoadingProgressFill>>,
+                        mut counters: Query<&mut Text, With<LoadingProgressCounter>>,
+                        mut commands: Commands,
+                        mut state: ResMut<State<GameState>>)
+{
+    let dependencies = species_tables.iter().flat_map(|(_, table)| table.dependencies());
+    let handles: Vec<HandleUntyped> = tracked.0.iter().cloned().chain(dependencies).collect();
+
+    let mut loaded = 0;
+
+    for handle in &handles {
+        match asset_server.get_load_state(handle) {
+            LoadState::Loaded => loaded += 1,
+            LoadState::Failed => {
+                commands.insert_resource(LoadingFailure {
+                    path: asset_path(&asset_server, handle),
+                    reason: None,
+                });
+                // Otherwise the asset loader keeps waiting for it forever. Overwrites the asset loader moving on to
+                // gameplay in the same frame, when the failed asset wasn't part of its collections.
+                state.overwrite_set(GameState::LoadingFailed).unwrap();
+                return;
+            },
+            _ => (),
+        }
+    }
+
+    for mut style in fills.iter_mut() {
+        style.size.width = Val::Percent(loaded as f32 / handles.len() as f32 * 100.0);
+    }
+
+    for mut text in counters.iter_mut() {
+        text.sections[0].value = localization.format("loading.progress", &[("loaded", &loaded), ("total", &handles.len())]);
     }
-}
\ No newline at end of file
+}
+
+pub fn loading_failed_start(mut commands: Commands,
+                            assets: Res<PreloadedAssets>,
+                            failure: Res<LoadingFailure>,
+                            theme: Res<Theme>,
+                            localization: Res<Localization>)
+{
+    let message = match &failure.reason {
+        Some(reason) => localization.format("loading.invalid", &[("asset", &failure.path), ("reason", reason)]),
+        None => localization.format("loading.failed", &[("asset", &failure.path)]),
+    };
+
+    commands.spawn_bundle(UiCameraBundle::default()).insert(StateScoped(GameState::LoadingFailed));
+
+    commands.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }).insert(StateScoped(GameState::LoadingFailed)).with_children(|parent| {
+        parent.spawn_bundle(TextBundle {
+            text: Text::with_section(message, TextStyle {
+                font: assets.font.clone(),
+                color: theme.error,
+                font_size: 56.0,
+            }, TextAlignment {
+                horizontal: HorizontalAlign::Center,
+                vertical: VerticalAlign::Center,
+            }),
+            transform: Transform {
+                scale: vec3(0.25, 0.25, 1.0) * 2.0,
+                ..default()
+            },
+            ..default()
+        });
+    });
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+use crate::settings::Settings;
+
+/// Codes of the languages with a string table in `assets/lang`.
+///
+/// Only languages the bundled font has glyphs for can be added, it covers ASCII only.
+pub const LANGUAGES: [&str; 2] = ["en", "nl"];
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+/// Embedded, so there is text to show before any assets are loaded.
+const FALLBACK_TABLE: &str = include_str!("../assets/lang/en.lang.ron");
+
+/// Translated UI strings of a single language, loaded from a `.lang.ron` file.
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "3e8d6c1a-5b27-4f90-8a4e-7c2d9b1f6e53"]
+pub struct StringTable {
+    /// Name of the language in the language itself.
+    pub name: String,
+    pub strings: HashMap<String, String>,
+}
+
+#[derive(Default)]
+pub struct StringTableLoader;
+
+impl AssetLoader for StringTableLoader {
+    fn load<'a>(&'a self, bytes: &'a [u8], load_context: &'a mut LoadContext) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let table: StringTable = ron::de::from_bytes(bytes)?;
+
+            load_context.set_default_asset(LoadedAsset::new(table));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["lang.ron"]
+    }
+}
+
+/// Strings of the selected language, falling back to English for anything missing.
+pub struct Localization {
+    language: String,
+    strings: HashMap<String, String>,
+    fallback: StringTable,
+    /// Kept so that every table stays loaded and the language can be switched at any time.
+    tables: HashMap<String, Handle<StringTable>>,
+    names: HashMap<String, String>,
+}
+
+impl FromWorld for Localization {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+
+        let tables = LANGUAGES.iter()
+            .map(|code| (code.to_string(), asset_server.load(&format!("lang/{}.lang.ron", code))))
+            .collect();
+
+        let fallback: StringTable = ron::de::from_str(FALLBACK_TABLE).expect("invalid embedded string table");
+        let names = HashMap::from([(FALLBACK_LANGUAGE.to_string(), fallback.name.clone())]);
+
+        Localization {
+            language: FALLBACK_LANGUAGE.to_string(),
+            strings: HashMap::new(),
+            fallback,
+            tables,
+            names,
+        }
+    }
+}
+
+impl Localization {
+    /// Code of the language the strings are currently in.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Handles of the string tables of all languages.
+    pub fn tables(&self) -> impl Iterator<Item = &Handle<StringTable>> {
+        self.tables.values()
+    }
+
+    /// Name of a language in the language itself, or its code if its table isn't loaded.
+    pub fn language_name<'a>(&'a self, code: &'a str) -> &'a str {
+        self.names.get(code).map_or(code, String::as_str)
+    }
+
+    /// The string for `key`, or `None` if no table has it.
+    pub fn try_get(&self, key: &str) -> Option<&str> {
+        self.strings.get(key)
+            .or_else(|| self.fallback.strings.get(key))
+            .map(String::as_str)
+    }
+
+    /// The string for `key`. Missing strings show up as the key itself, so they are easy to spot.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.try_get(key).unwrap_or(key)
+    }
+
+    /// The string for `key`, with every `{name}` replaced by the matching argument.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut string = self.get(key).to_string();
+
+        for (name, value) in args {
+            string = string.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+
+        string
+    }
+}
+
+/// Text which is a single localized string, updated when the language changes.
+#[derive(Component)]
+pub struct LocalizedText(pub &'static str);
+
+pub fn register_systems(app: &mut App) {
+    app.add_asset::<StringTable>()
+        .init_asset_loader::<StringTableLoader>()
+        .init_resource::<Localization>();
+
+    app.add_system(switch_language)
+        .add_system(localized_texts.after(switch_language));
+}
+
+/// Copies the strings of the selected language once its table is loaded, and again whenever it is reloaded.
+pub fn switch_language(mut localization: ResMut<Localization>,
+                       mut events: EventReader<AssetEvent<StringTable>>,
+                       tables: Res<Assets<StringTable>>,
+                       settings: Res<Settings>)
+{
+    let mut tables_changed = false;
+    for event in events.iter() {
+        if let AssetEvent::Created { .. } | AssetEvent::Modified { .. } = event {
+            tables_changed = true;
+        }
+    }
+
+    if !tables_changed && !settings.is_changed() {
+        return;
+    }
+
+    // Only touched when something is different, everything showing text updates when it changes
+    let names: HashMap<String, String> = localization.tables.iter()
+        .filter_map(|(code, handle)| tables.get(handle).map(|table| (code.clone(), table.name.clone())))
+        .collect();
+    if names.len() > localization.names.len() {
+        localization.names = names;
+    }
+
+    let code = if localization.tables.contains_key(&settings.language) {
+        settings.language.as_str()
+    } else {
+        FALLBACK_LANGUAGE
+    };
+
+    let table = match tables.get(&localization.tables[code]) {
+        Some(table) => table,
+        None => return,
+    };
+
+    if localization.language != code || localization.strings != table.strings {
+        localization.language = code.to_string();
+        localization.strings = table.strings.clone();
+    }
+}
+
+pub fn localized_texts(mut texts: Query<(&mut Text, &LocalizedText)>,
+                       added: Query<(), Added<LocalizedText>>,
+                       localization: Res<Localization>)
+{
+    if added.is_empty() && !localization.is_changed() {
+        return;
+    }
+
+    for (mut text, localized) in texts.iter_mut() {
+        text.sections[0].value = localization.get(localized.0).to_string();
+    }
+}
@@ -1,16 +1,36 @@
 use bevy::math::vec3;
 use bevy::prelude::*;
 use bevy::ui::Val::Percent;
-use crate::{palette, PreloadedAssets};
-
-pub fn lose_start(mut commands: Commands, assets: Res<PreloadedAssets>,
-                  mut entities: Query<Entity>) {
-    for entity in entities.iter_mut() {
-        commands.entity(entity).despawn();
-    }
+use crate::{GameState, PreloadedAssets};
+use crate::cleanup::StateScoped;
+use crate::localization::LocalizedText;
+use crate::theme::{spawn_panel, Skin, Theme};
 
+pub fn lose_start(mut commands: Commands, assets: Res<PreloadedAssets>, theme: Res<Theme>) {
     let mut camera = UiCameraBundle::default();
-    commands.spawn_bundle(camera);
+    commands.spawn_bundle(camera).insert(StateScoped(GameState::Lose));
+
+    let text = commands.spawn_bundle(TextBundle {
+        text: Text::with_section("", TextStyle {
+            font: assets.font.clone(),
+            color: theme.text,
+            font_size: 56.0,
+        }, TextAlignment {
+            horizontal: HorizontalAlign::Center,
+            vertical: VerticalAlign::Center,
+        }),
+        transform: Transform {
+            scale: vec3(0.25, 0.25, 1.0) * 3.0,
+            ..default()
+        },
+        style: Style {
+            margin: Rect::all(Val::Px(24.0)),
+            ..default()
+        },
+        ..default()
+    }).insert(LocalizedText("defeat")).id();
+
+    let dialog = spawn_panel(&mut commands, &theme, Skin::Dialog, default(), text);
 
     commands.spawn_bundle(NodeBundle {
         style: Style {
@@ -21,21 +41,5 @@ pub fn lose_start(mut commands: Commands, assets: Res<PreloadedAssets>,
         },
         color: Color::NONE.into(),
         ..default()
-    }).with_children(|parent| {
-        parent.spawn_bundle(TextBundle {
-            text: Text::with_section("Defeat!", TextStyle {
-                font: assets.font.clone(),
-                color: palette::WHITE,
-                font_size: 56.0,
-            }, TextAlignment {
-                horizontal: HorizontalAlign::Center,
-                vertical: VerticalAlign::Center,
-            }),
-            transform: Transform {
-                scale: vec3(0.25, 0.25, 1.0) * 3.0,
-                ..default()
-            },
-            ..default()
-        });
-    });
+    }).insert(StateScoped(GameState::Lose)).push_children(&[dialog]);
 }
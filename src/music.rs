@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+use bevy_kira_audio::{AudioApp, AudioChannel};
+use crate::assets::MusicAssets;
+use crate::GameState;
+use crate::gameplay::components::Monster;
+use crate::settings::Settings;
+
+/// Seconds it takes to fade a layer or a track fully in or out.
+const FADE_TIME: f32 = 2.0;
+/// Number of monsters at which the danger is at its highest.
+const DANGER_MONSTER_COUNT: f32 = 12.0;
+/// Distance from the core at which an approaching monster starts adding danger, same as where monsters spawn.
+const DANGER_DISTANCE: f32 = 240.0;
+
+/// Channel for the single-stem tracks: menu and defeat music.
+pub struct TrackChannel;
+/// Gameplay stem which is always audible.
+pub struct BaseLayerChannel;
+/// Gameplay stem brought in when monsters start piling up.
+pub struct TensionLayerChannel;
+/// Gameplay stem brought in when the core is about to be reached.
+pub struct DangerLayerChannel;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MusicTrack {
+    /// Plays on the pause screen, which is the only menu.
+    Menu,
+    Gameplay,
+    Lose,
+}
+
+impl MusicTrack {
+    fn for_state(state: &GameState) -> Option<MusicTrack> {
+        match state {
+            GameState::LoadingAssets | GameState::LoadingFailed => None,
+            GameState::Gameplay => Some(MusicTrack::Gameplay),
+            GameState::Paused => Some(MusicTrack::Menu),
+            GameState::Lose => Some(MusicTrack::Lose),
+        }
+    }
+}
+
+/// Moves a channel volume towards a target, a little every frame.
+#[derive(Default)]
+struct Fader {
+    volume: f32,
+    target: f32,
+}
+
+impl Fader {
+    /// Returns whether the volume changed.
+    fn step(&mut self, delta: f32) -> bool {
+        let max_step = delta / FADE_TIME;
+        let volume = self.volume + (self.target - self.volume).clamp(-max_step, max_step);
+        let changed = volume != self.volume;
+
+        self.volume = volume;
+        changed
+    }
+
+    fn fade_in_from_silence(&mut self) {
+        self.volume = 0.0;
+        self.target = 1.0;
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Playback {
+    Stopped,
+    Playing,
+    Paused,
+}
+
+/// State of the music channels.
+pub struct Music {
+    track: Option<MusicTrack>,
+    /// How threatening the game currently is, from 0 to 1.
+    pub danger: f32,
+    track_playback: Playback,
+    track_fader: Fader,
+    layers_playback: Playback,
+    base_fader: Fader,
+    tension_fader: Fader,
+    danger_fader: Fader,
+}
+
+impl Default for Music {
+    fn default() -> Self {
+        Music {
+            track: None,
+            danger: 0.0,
+            track_playback: Playback::Stopped,
+            track_fader: Fader::default(),
+            layers_playback: Playback::Stopped,
+            base_fader: Fader::default(),
+            tension_fader: Fader::default(),
+            danger_fader: Fader::default(),
+        }
+    }
+}
+
+pub fn register_systems(app: &mut App) {
+    app.add_audio_channel::<TrackChannel>()
+        .add_audio_channel::<BaseLayerChannel>()
+        .add_audio_channel::<TensionLayerChannel>()
+        .add_audio_channel::<DangerLayerChannel>()
+        .init_resource::<Music>();
+
+    app.add_system(switch_track)
+        .add_system(fade_music.after(switch_track));
+
+    app.add_system_set(SystemSet::on_update(GameState::Gameplay).with_system(measure_danger));
+}
+
+/// Danger grows with the number of monsters and how close the nearest one is to the core.
+pub fn measure_danger(monsters: Query<&Transform, With<Monster>>,
+                      mut music: ResMut<Music>)
+{
+    let count = monsters.iter().count() as f32 / DANGER_MONSTER_COUNT;
+
+    // The core is at the world origin
+    let nearest = monsters.iter()
+        .map(|transform| transform.translation.truncate().length())
+        .fold(f32::MAX, f32::min);
+    let proximity = 1.0 - nearest / DANGER_DISTANCE;
+
+    music.danger = count.max(proximity).clamp(0.0, 1.0);
+}
+
+/// Starts the track of the current state, fading out whatever played before.
+pub fn switch_track(mut music: ResMut<Music>,
+                    state: Res<State<GameState>>,
+                    music_assets: Option<Res<MusicAssets>>,
+                    track_channel: Res<AudioChannel<TrackChannel>>,
+                    base: Res<AudioChannel<BaseLayerChannel>>,
+                    tension: Res<AudioChannel<TensionLayerChannel>>,
+                    danger: Res<AudioChannel<DangerLayerChannel>>)
+{
+    let music_assets = match music_assets {
+        Some(music_assets) => music_assets,
+        None => return,
+    };
+
+    let track = MusicTrack::for_state(state.current());
+    if track == music.track {
+        return;
+    }
+    music.track = track;
+
+    match track {
+        Some(MusicTrack::Gameplay) => {
+            match music.layers_playback {
+                Playback::Stopped => {
+                    // Started together, so the stems stay in sync
+                    base.play_looped(music_assets.gameplay_base.clone());
+                    tension.play_looped(music_assets.gameplay_tension.clone());
+                    danger.play_looped(music_assets.gameplay_danger.clone());
+
+                    // Channels start at full volume, the faders only update them when they move
+                    base.set_volume(0.0);
+                    tension.set_volume(0.0);
+                    danger.set_volume(0.0);
+                    music.base_fader.fade_in_from_silence();
+                },
+                Playback::Paused => {
+                    base.resume();
+                    tension.resume();
+                    danger.resume();
+                },
+                Playback::Playing => (),
+            }
+            music.layers_playback = Playback::Playing;
+
+            music.track_fader.target = 0.0;
+        },
+        Some(MusicTrack::Menu) | Some(MusicTrack::Lose) => {
+            let handle = if track == Some(MusicTrack::Menu) {
+                music_assets.menu.clone()
+            } else {
+                music_assets.lose.clone()
+            };
+
+            track_channel.stop();
+            track_channel.play_looped(handle);
+            music.track_playback = Playback::Playing;
+            music.track_fader.fade_in_from_silence();
+        },
+        None => music.track_fader.target = 0.0,
+    }
+}
+
+/// Crossfades the gameplay layers according to the danger, and fades tracks in and out.
+pub fn fade_music(mut music: ResMut<Music>,
+                  track_channel: Res<AudioChannel<TrackChannel>>,
+                  base: Res<AudioChannel<BaseLayerChannel>>,
+                  tension: Res<AudioChannel<TensionLayerChannel>>,
+                  danger: Res<AudioChannel<DangerLayerChannel>>,
+                  settings: Res<Settings>,
+                  time: Res<Time>)
+{
+    let music = &mut *music;
+    let delta = time.delta_seconds();
+
+    if music.track == Some(MusicTrack::Gameplay) {
+        music.base_fader.target = 1.0;
+        music.tension_fader.target = ((music.danger - 0.15) / 0.25).clamp(0.0, 1.0);
+        music.danger_fader.target = ((music.danger - 0.5) / 0.3).clamp(0.0, 1.0);
+    } else {
+        music.base_fader.target = 0.0;
+        music.tension_fader.target = 0.0;
+        music.danger_fader.target = 0.0;
+    }
+
+    // The faders only go from 0 to 1, the volume settings scale all of them
+    let volume = settings.effective_music_volume();
+    let settings_changed = settings.is_changed();
+
+    if music.track_fader.step(delta) || settings_changed {
+        track_channel.set_volume(music.track_fader.volume * volume);
+    }
+    if music.base_fader.step(delta) || settings_changed {
+        base.set_volume(music.base_fader.volume * volume);
+    }
+    if music.tension_fader.step(delta) || settings_changed {
+        tension.set_volume(music.tension_fader.volume * volume);
+    }
+    if music.danger_fader.step(delta) || settings_changed {
+        danger.set_volume(music.danger_fader.volume * volume);
+    }
+
+    // Silent channels are paused rather than left playing, so the gameplay music resumes where it left off
+    let layers_silent = music.base_fader.volume == 0.0 && music.tension_fader.volume == 0.0 && music.danger_fader.volume == 0.0;
+    if music.track != Some(MusicTrack::Gameplay) && music.layers_playback == Playback::Playing && layers_silent {
+        base.pause();
+        tension.pause();
+        danger.pause();
+        music.layers_playback = Playback::Paused;
+    }
+
+    let track_silent = music.track_fader.volume == 0.0;
+    if !matches!(music.track, Some(MusicTrack::Menu) | Some(MusicTrack::Lose)) && music.track_playback == Playback::Playing && track_silent {
+        track_channel.stop();
+        music.track_playback = Playback::Stopped;
+    }
+}
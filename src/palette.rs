@@ -4,5 +4,4 @@ pub const BLACK: Color = Color::rgb(16.0 / 255.0, 19.0 / 255.0, 23.0 / 255.0);
 pub const WHITE: Color = Color::rgb(213.0 / 255.0, 230.0 / 255.0, 235.0 / 255.0);
 
 pub const RED: Color = Color::rgb(255.0 / 255.0, 26.0 / 255.0, 0.0 / 255.0);
-pub const GREEN: Color = Color::rgb(209.0 / 255.0, 255.0 / 255.0, 0.0 / 255.0);
-pub const BLUE: Color = Color::rgb(0.0 / 255.0, 195.0 / 255.0, 255.0 / 255.0);
\ No newline at end of file
+pub const GREEN: Color = Color::rgb(209.0 / 255.0, 255.0 / 255.0, 0.0 / 255.0);
\ No newline at end of file
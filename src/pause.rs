@@ -0,0 +1,538 @@
+use bevy::input::mouse::MouseWheel;
+use bevy::math::{const_vec2, vec3};
+use bevy::prelude::*;
+use crate::{GameState, PreloadedAssets};
+use crate::cleanup::StateScoped;
+use crate::input::{Action, ActionState, Binding, Bindings, InputButton};
+use crate::localization::{Localization, LocalizedText, LANGUAGES};
+use crate::settings::{Settings, Toggle, Volume};
+use crate::theme::{spawn_panel, Skin, Theme};
+
+/// Size of a volume slider, in screen pixels.
+const VOLUME_SLIDER_SIZE: Vec2 = const_vec2!([160.0, 12.0]);
+
+#[derive(Component)]
+pub struct RebindButton(pub Action);
+
+#[derive(Component)]
+pub struct RebindLabel(pub Action);
+
+#[derive(Component)]
+pub struct ResetBindingsButton;
+
+#[derive(Component)]
+pub struct ResumeHint;
+
+#[derive(Component)]
+pub struct ToggleButton(pub Toggle);
+
+#[derive(Component)]
+pub struct ToggleLabel(pub Toggle);
+
+/// Switches to the next language when clicked.
+#[derive(Component)]
+pub struct LanguageButton;
+
+#[derive(Component)]
+pub struct LanguageLabel;
+
+#[derive(Component)]
+pub struct VolumeSlider(pub Volume);
+
+#[derive(Component)]
+pub struct VolumeFill(pub Volume);
+
+#[derive(Component)]
+pub struct VolumeLabel(pub Volume);
+
+/// Action waiting for the player to press its new button.
+#[derive(Default)]
+pub struct Rebinding(pub Option<Action>);
+
+pub fn register_systems(app: &mut App) {
+    app.init_resource::<Rebinding>()
+        .add_system(toggle_pause);
+
+    app.add_system_set(SystemSet::on_enter(GameState::Paused).with_system(pause_enter))
+        .add_system_set(SystemSet::on_update(GameState::Paused)
+            .with_system(rebind_buttons)
+            .with_system(reset_bindings)
+            .with_system(capture_binding)
+            .with_system(rebind_labels)
+            .with_system(toggle_buttons)
+            .with_system(toggle_labels)
+            .with_system(volume_sliders)
+            .with_system(volume_labels)
+            .with_system(language_button)
+            .with_system(language_label));
+}
+
+fn toggle_pause(actions: Res<ActionState>,
+                rebinding: Res<Rebinding>,
+                mut state: ResMut<State<GameState>>)
+{
+    // The pause button might be the one that was just bound
+    if !actions.just_pressed(Action::Pause) || rebinding.0.is_some() || rebinding.is_changed() {
+        return;
+    }
+
+    // Fails if the state already changes this frame, e.g. because the game was just lost, which goes first
+    let _ = match state.current() {
+        GameState::Gameplay => state.push(GameState::Paused),
+        GameState::Paused => state.pop(),
+        _ => Ok(()),
+    };
+}
+
+fn pause_enter(mut commands: Commands, assets: Res<PreloadedAssets>, theme: Res<Theme>, mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+
+    let text_style = TextStyle {
+        font: assets.font.clone(),
+        font_size: 56.0,
+        color: theme.text,
+    };
+
+    let content = commands.spawn_bundle(NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::Center,
+            margin: Rect::all(Val::Px(12.0)),
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn_bundle(TextBundle {
+            text: Text::with_section("", text_style.clone(), default()),
+            transform: Transform {
+                scale: vec3(0.25, 0.25, 1.0) * 3.0,
+                ..default()
+            },
+            style: Style {
+                margin: Rect {
+                    bottom: Val::Px(24.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        }).insert(LocalizedText("pause.title"));
+
+        // Two columns, otherwise the list doesn't fit on smaller screens
+        let actions = Action::all();
+        let (left, right) = actions.split_at(actions.len().div_ceil(2));
+
+        parent.spawn_bundle(NodeBundle {
+            color: Color::NONE.into(),
+            ..default()
+        }).with_children(|columns| {
+            for column_actions in [left, right] {
+                columns.spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::FlexStart,
+                        margin: Rect::all(Val::Px(12.0)),
+                        ..default()
+                    },
+                    color: Color::NONE.into(),
+                    ..default()
+                }).with_children(|column| {
+                    for action in column_actions {
+                        insert_rebind_button(column, *action, &text_style, &theme);
+                    }
+                });
+            }
+        });
+
+        parent.spawn_bundle(ButtonBundle {
+            color: theme.button.into(),
+            style: Style {
+                margin: Rect {
+                    top: Val::Px(24.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        }).insert(ResetBindingsButton).with_children(|button| {
+            button.spawn_bundle(TextBundle {
+                text: Text::with_section("", text_style.clone(), default()),
+                transform: Transform {
+                    scale: vec3(0.25, 0.25, 1.0) * 2.0,
+                    ..default()
+                },
+                ..default()
+            }).insert(LocalizedText("pause.reset_bindings"));
+        });
+
+        parent.spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexEnd,
+                margin: Rect {
+                    top: Val::Px(24.0),
+                    ..default()
+                },
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        }).with_children(|column| {
+            for volume in Volume::ALL {
+                insert_volume_slider(column, volume, &text_style, &theme);
+            }
+        });
+
+        parent.spawn_bundle(NodeBundle {
+            style: Style {
+                margin: Rect {
+                    top: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        }).with_children(|row| {
+            for toggle in Toggle::ALL {
+                row.spawn_bundle(ButtonBundle {
+                    color: theme.button.into(),
+                    style: Style {
+                        margin: Rect::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    ..default()
+                }).insert(ToggleButton(toggle)).with_children(|button| {
+                    button.spawn_bundle(TextBundle {
+                        text: Text::with_section("", text_style.clone(), default()),
+                        transform: Transform {
+                            scale: vec3(0.25, 0.25, 1.0) * 2.0,
+                            ..default()
+                        },
+                        ..default()
+                    }).insert(ToggleLabel(toggle));
+                });
+            }
+
+            row.spawn_bundle(ButtonBundle {
+                color: theme.button.into(),
+                style: Style {
+                    margin: Rect::all(Val::Px(6.0)),
+                    ..default()
+                },
+                ..default()
+            }).insert(LanguageButton).with_children(|button| {
+                button.spawn_bundle(TextBundle {
+                    text: Text::with_section("", text_style.clone(), default()),
+                    transform: Transform {
+                        scale: vec3(0.25, 0.25, 1.0) * 2.0,
+                        ..default()
+                    },
+                    ..default()
+                }).insert(LanguageLabel);
+            });
+        });
+
+        parent.spawn_bundle(TextBundle {
+            text: Text::with_section("", text_style.clone(), default()),
+            transform: Transform {
+                scale: vec3(0.25, 0.25, 1.0) * 2.0,
+                ..default()
+            },
+            style: Style {
+                margin: Rect {
+                    top: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        }).insert(ResumeHint);
+    }).id();
+
+    let dialog = spawn_panel(&mut commands, &theme, Skin::Dialog, default(), content);
+
+    commands.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        color: theme.overlay.into(),
+        ..default()
+    }).insert(StateScoped(GameState::Paused)).push_children(&[dialog]);
+}
+
+fn insert_volume_slider(parent: &mut ChildBuilder, volume: Volume, text_style: &TextStyle, theme: &Theme) {
+    parent.spawn_bundle(NodeBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            margin: Rect::all(Val::Px(4.0)),
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }).with_children(|row| {
+        row.spawn_bundle(TextBundle {
+            text: Text::with_section("", text_style.clone(), default()),
+            transform: Transform {
+                scale: vec3(0.25, 0.25, 1.0) * 2.0,
+                ..default()
+            },
+            style: Style {
+                margin: Rect {
+                    right: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        }).insert(VolumeLabel(volume));
+
+        row.spawn_bundle(ButtonBundle {
+            color: theme.track.into(),
+            style: Style {
+                size: Size::new(Val::Px(VOLUME_SLIDER_SIZE.x), Val::Px(VOLUME_SLIDER_SIZE.y)),
+                ..default()
+            },
+            ..default()
+        }).insert(VolumeSlider(volume)).with_children(|slider| {
+            slider.spawn_bundle(NodeBundle {
+                color: theme.fill.into(),
+                style: Style {
+                    size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                    ..default()
+                },
+                ..default()
+            }).insert(VolumeFill(volume));
+        });
+    });
+}
+
+fn insert_rebind_button(parent: &mut ChildBuilder, action: Action, text_style: &TextStyle, theme: &Theme) {
+    parent.spawn_bundle(ButtonBundle {
+        color: theme.button.into(),
+        ..default()
+    }).insert(RebindButton(action)).with_children(|button| {
+        button.spawn_bundle(TextBundle {
+            text: Text::with_section("", text_style.clone(), default()),
+            transform: Transform {
+                scale: vec3(0.25, 0.25, 1.0) * 1.5,
+                ..default()
+            },
+            ..default()
+        }).insert(RebindLabel(action));
+    });
+}
+
+fn rebind_buttons(query: Query<(&Interaction, &RebindButton), Changed<Interaction>>,
+                  mut rebinding: ResMut<Rebinding>)
+{
+    for (interaction, button) in query.iter() {
+        if *interaction == Interaction::Clicked {
+            rebinding.0 = Some(button.0);
+        }
+    }
+}
+
+fn reset_bindings(query: Query<&Interaction, (Changed<Interaction>, With<ResetBindingsButton>)>,
+                  mut bindings: ResMut<Bindings>,
+                  mut rebinding: ResMut<Rebinding>)
+{
+    for interaction in query.iter() {
+        if *interaction == Interaction::Clicked {
+            *bindings = Bindings::default();
+            bindings.save();
+            rebinding.0 = None;
+        }
+    }
+}
+
+const MODIFIER_KEYS: [KeyCode; 4] = [KeyCode::LControl, KeyCode::RControl, KeyCode::LShift, KeyCode::RShift];
+
+/// Binds the first button pressed after a rebind button was clicked.
+fn capture_binding(mut rebinding: ResMut<Rebinding>,
+                   mut bindings: ResMut<Bindings>,
+                   keys: Res<Input<KeyCode>>,
+                   mouse_buttons: Res<Input<MouseButton>>,
+                   gamepad_buttons: Res<Input<GamepadButton>>,
+                   mut mouse_wheel: EventReader<MouseWheel>)
+{
+    let wheel = mouse_wheel.iter().fold(0.0, |wheel, event| wheel + event.y);
+
+    let action = match rebinding.0 {
+        // Skip the frame of the click, so the click itself doesn't get bound
+        Some(action) if !rebinding.is_changed() => action,
+        _ => return,
+    };
+
+    if keys.just_pressed(KeyCode::Escape) {
+        rebinding.0 = None;
+        return;
+    }
+
+    let key = keys.get_just_pressed().find(|key| !MODIFIER_KEYS.contains(key));
+    let mouse_button = mouse_buttons.get_just_pressed().next();
+    let gamepad_button = gamepad_buttons.get_just_pressed().next();
+
+    let button = if let Some(key) = key {
+        InputButton::Key(*key)
+    } else if let Some(mouse_button) = mouse_button {
+        InputButton::Mouse(*mouse_button)
+    } else if let Some(GamepadButton(_, button)) = gamepad_button {
+        InputButton::Gamepad(*button)
+    } else if wheel > 0.0 {
+        InputButton::WheelUp
+    } else if wheel < 0.0 {
+        InputButton::WheelDown
+    } else {
+        return;
+    };
+
+    let binding = Binding {
+        button,
+        ctrl: keys.any_pressed([KeyCode::LControl, KeyCode::RControl]),
+        shift: keys.any_pressed([KeyCode::LShift, KeyCode::RShift]),
+    };
+
+    bindings.rebind(action, binding);
+    bindings.save();
+    rebinding.0 = None;
+}
+
+fn rebind_labels(mut labels: Query<(&mut Text, &RebindLabel)>,
+                 mut hint: Query<&mut Text, (With<ResumeHint>, Without<RebindLabel>)>,
+                 added: Query<(), Added<RebindLabel>>,
+                 bindings: Res<Bindings>,
+                 rebinding: Res<Rebinding>,
+                 localization: Res<Localization>)
+{
+    if added.is_empty() && !bindings.is_changed() && !rebinding.is_changed() && !localization.is_changed() {
+        return;
+    }
+
+    for (mut text, label) in labels.iter_mut() {
+        let bound = if rebinding.0 == Some(label.0) {
+            localization.get("pause.rebinding").to_string()
+        } else {
+            bindings.names(label.0, &localization)
+        };
+
+        text.sections[0].value = localization.format("pause.binding", &[("action", &label.0.name(&localization)), ("buttons", &bound)]);
+    }
+
+    for mut text in hint.iter_mut() {
+        text.sections[0].value = localization.format("pause.hint", &[("button", &bindings.names(Action::Pause, &localization))]);
+    }
+}
+
+fn toggle_buttons(query: Query<(&Interaction, &ToggleButton), Changed<Interaction>>,
+                  mut settings: ResMut<Settings>)
+{
+    for (interaction, button) in query.iter() {
+        if *interaction == Interaction::Clicked {
+            let value = button.0.get(&settings);
+            button.0.set(&mut settings, !value);
+
+            settings.save();
+        }
+    }
+}
+
+fn toggle_labels(mut labels: Query<(&mut Text, &ToggleLabel)>,
+                 added: Query<(), Added<ToggleLabel>>,
+                 settings: Res<Settings>,
+                 localization: Res<Localization>)
+{
+    if added.is_empty() && !settings.is_changed() && !localization.is_changed() {
+        return;
+    }
+
+    for (mut text, label) in labels.iter_mut() {
+        let value = label.0.get(&settings);
+        let value = localization.get(if value { "settings.on" } else { "settings.off" });
+
+        text.sections[0].value = format!("{}: {}", localization.get(label.0.key()), value);
+    }
+}
+
+fn language_button(query: Query<&Interaction, (Changed<Interaction>, With<LanguageButton>)>,
+                   mut settings: ResMut<Settings>)
+{
+    for interaction in query.iter() {
+        if *interaction == Interaction::Clicked {
+            let current = LANGUAGES.iter().position(|code| *code == settings.language).unwrap_or(0);
+            settings.language = LANGUAGES[(current + 1) % LANGUAGES.len()].to_string();
+
+            settings.save();
+        }
+    }
+}
+
+fn language_label(mut labels: Query<&mut Text, With<LanguageLabel>>,
+                  added: Query<(), Added<LanguageLabel>>,
+                  localization: Res<Localization>)
+{
+    if added.is_empty() && !localization.is_changed() {
+        return;
+    }
+
+    for mut text in labels.iter_mut() {
+        let name = localization.language_name(localization.language());
+
+        text.sections[0].value = format!("{}: {}", localization.get("settings.language"), name);
+    }
+}
+
+/// Sets the volume from the cursor position while a slider is held, and saves it once released.
+fn volume_sliders(sliders: Query<(&Interaction, &Node, &GlobalTransform, &VolumeSlider)>,
+                  mut settings: ResMut<Settings>,
+                  mut dragging: Local<bool>,
+                  windows: Res<Windows>)
+{
+    let cursor = windows.get_primary().and_then(|window| window.cursor_position());
+    let held = sliders.iter().find(|(interaction, ..)| **interaction == Interaction::Clicked);
+
+    match (held, cursor) {
+        (Some((_, node, transform, slider)), Some(cursor)) => {
+            // UI nodes are positioned by their center
+            let left = transform.translation.x - node.size.x / 2.0;
+            let fraction = ((cursor.x - left) / node.size.x).clamp(0.0, 1.0);
+            // Steps of 5% are fine enough, and keep the labels readable
+            let value = (fraction * 20.0).round() / 20.0;
+
+            if slider.0.get(&settings) != value {
+                slider.0.set(&mut settings, value);
+            }
+            *dragging = true;
+        },
+        _ => if *dragging {
+            settings.save();
+            *dragging = false;
+        },
+    }
+}
+
+fn volume_labels(mut labels: Query<(&mut Text, &VolumeLabel)>,
+                 mut fills: Query<(&mut Style, &VolumeFill)>,
+                 added: Query<(), Added<VolumeLabel>>,
+                 settings: Res<Settings>,
+                 localization: Res<Localization>)
+{
+    if added.is_empty() && !settings.is_changed() && !localization.is_changed() {
+        return;
+    }
+
+    for (mut text, label) in labels.iter_mut() {
+        let value = label.0.get(&settings);
+
+        text.sections[0].value = format!("{}: {}%", localization.get(label.0.key()), (value * 100.0).round());
+    }
+
+    for (mut style, fill) in fills.iter_mut() {
+        style.size.width = Val::Percent(fill.0.get(&settings) * 100.0);
+    }
+}
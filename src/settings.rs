@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::config;
+use crate::localization::FALLBACK_LANGUAGE;
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+/// Player preferences, saved whenever they change.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Damage numbers and reward popups.
+    pub floating_text: bool,
+    /// Volumes from 0 to 1, the master volume scales the other two.
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    /// Silences all audio without losing the volumes.
+    pub muted: bool,
+    /// Turns off screen shake and hit-stop.
+    pub reduced_motion: bool,
+    /// Freezes the action for a moment on heavy hits.
+    pub hit_stop: bool,
+    /// Code of the UI language, see [`LANGUAGES`](crate::localization::LANGUAGES).
+    pub language: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            floating_text: true,
+            master_volume: 1.0,
+            music_volume: 0.7,
+            sfx_volume: 1.0,
+            muted: false,
+            reduced_motion: false,
+            hit_stop: true,
+            language: FALLBACK_LANGUAGE.to_string(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        config::load(SETTINGS_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        config::save(SETTINGS_FILE, self);
+    }
+
+    /// Volume the music channels are scaled by.
+    pub fn effective_music_volume(&self) -> f32 {
+        if self.muted { 0.0 } else { self.master_volume * self.music_volume }
+    }
+
+    /// Volume of the sound effects channel.
+    pub fn effective_sfx_volume(&self) -> f32 {
+        if self.muted { 0.0 } else { self.master_volume * self.sfx_volume }
+    }
+}
+
+/// A setting which can be switched on and off from the pause screen.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Toggle {
+    FloatingText,
+    Mute,
+    ReducedMotion,
+    HitStop,
+}
+
+impl Toggle {
+    pub const ALL: [Toggle; 4] = [Toggle::FloatingText, Toggle::Mute, Toggle::ReducedMotion, Toggle::HitStop];
+
+    /// Localization key of the name.
+    pub fn key(self) -> &'static str {
+        match self {
+            Toggle::FloatingText => "toggle.floating_text",
+            Toggle::Mute => "toggle.mute",
+            Toggle::ReducedMotion => "toggle.reduced_motion",
+            Toggle::HitStop => "toggle.hit_stop",
+        }
+    }
+
+    pub fn get(self, settings: &Settings) -> bool {
+        match self {
+            Toggle::FloatingText => settings.floating_text,
+            Toggle::Mute => settings.muted,
+            Toggle::ReducedMotion => settings.reduced_motion,
+            Toggle::HitStop => settings.hit_stop,
+        }
+    }
+
+    pub fn set(self, settings: &mut Settings, value: bool) {
+        match self {
+            Toggle::FloatingText => settings.floating_text = value,
+            Toggle::Mute => settings.muted = value,
+            Toggle::ReducedMotion => settings.reduced_motion = value,
+            Toggle::HitStop => settings.hit_stop = value,
+        }
+    }
+}
+
+/// A volume which can be adjusted with a slider on the pause screen.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Volume {
+    Master,
+    Music,
+    Sfx,
+}
+
+impl Volume {
+    pub const ALL: [Volume; 3] = [Volume::Master, Volume::Music, Volume::Sfx];
+
+    /// Localization key of the name.
+    pub fn key(self) -> &'static str {
+        match self {
+            Volume::Master => "volume.master",
+            Volume::Music => "volume.music",
+            Volume::Sfx => "volume.sfx",
+        }
+    }
+
+    pub fn get(self, settings: &Settings) -> f32 {
+        match self {
+            Volume::Master => settings.master_volume,
+            Volume::Music => settings.music_volume,
+            Volume::Sfx => settings.sfx_volume,
+        }
+    }
+
+    pub fn set(self, settings: &mut Settings, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+
+        match self {
+            Volume::Master => settings.master_volume = value,
+            Volume::Music => settings.music_volume = value,
+            Volume::Sfx => settings.sfx_volume = value,
+        }
+    }
+}
+
+pub fn register_systems(app: &mut App) {
+    app.insert_resource(Settings::load());
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use bevy_ninepatch::{NinePatchBuilder, NinePatchBundle, NinePatchData};
+use crate::palette;
+
+/// Width of the border of the skin textures, in pixels. All of them share the same nine-patch margins.
+const SKIN_MARGIN: u32 = 9;
+
+/// Look of the UI, so that colours and textures can be swapped in one place.
+pub struct Theme {
+    /// HUD panels: the wallet bar and the buy panel.
+    pub panel: Handle<Image>,
+    pub tooltip: Handle<Image>,
+    /// Screens shown over the game, like the pause and defeat screens.
+    pub dialog: Handle<Image>,
+    pub nine_patch: Handle<NinePatchBuilder<()>>,
+    pub text: Color,
+    /// Background of plain buttons, like the ones on the pause screen.
+    pub button: Color,
+    /// Dims the game behind dialogs.
+    pub overlay: Color,
+    /// Empty part of sliders and progress bars.
+    pub track: Color,
+    /// Filled part of sliders and progress bars.
+    pub fill: Color,
+    /// Text telling that something went wrong.
+    pub error: Color,
+}
+
+/// Which of the theme textures a panel uses.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Skin {
+    Panel,
+    Tooltip,
+    Dialog,
+}
+
+impl Theme {
+    pub fn texture(&self, skin: Skin) -> Handle<Image> {
+        match skin {
+            Skin::Panel => self.panel.clone(),
+            Skin::Tooltip => self.tooltip.clone(),
+            Skin::Dialog => self.dialog.clone(),
+        }
+    }
+}
+
+impl FromWorld for Theme {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let panel = asset_server.load("ui/panel.png");
+        let tooltip = asset_server.load("ui/tooltip.png");
+        let dialog = asset_server.load("ui/dialog.png");
+
+        let mut nine_patches = world.resource_mut::<Assets<NinePatchBuilder<()>>>();
+        let nine_patch = nine_patches.add(NinePatchBuilder::by_margins(SKIN_MARGIN, SKIN_MARGIN, SKIN_MARGIN, SKIN_MARGIN));
+
+        Theme {
+            panel,
+            tooltip,
+            dialog,
+            nine_patch,
+            text: palette::WHITE,
+            button: palette::BLACK,
+            overlay: Color::rgba(0.0, 0.0, 0.0, 0.6),
+            track: Color::rgb(0.25, 0.25, 0.25),
+            fill: palette::WHITE,
+            error: palette::RED,
+        }
+    }
+}
+
+/// Spawns a nine-patch panel framing `content`, which should have a transparent background.
+///
+/// The content is moved into the middle of the panel once the texture is loaded.
+pub fn spawn_panel(commands: &mut Commands, theme: &Theme, skin: Skin, style: Style, content: Entity) -> Entity {
+    commands.spawn_bundle(NinePatchBundle {
+        style,
+        nine_patch_data: NinePatchData::with_single_content(theme.texture(skin), theme.nine_patch.clone(), content),
+        ..default()
+    }).id()
+}
+
+pub fn register_systems(app: &mut App) {
+    app.init_resource::<Theme>();
+}